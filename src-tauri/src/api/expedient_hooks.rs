@@ -14,6 +14,7 @@ impl JsCallback {
     }
 }
 
+/// Sends the expedient with its revision, that is given back to `update_expedient`
 #[tauri::command]
 pub fn hook_expedient(
     state: tauri::State<ApiState>,
@@ -25,7 +26,7 @@ pub fn hook_expedient(
     Some(
         database
             .as_mut()?
-            .hook_expedient(expedient_id, move |expedient| {
+            .hook_expedient_revision(expedient_id, move |expedient| {
                 js_callback.call(&window, &expedient);
             }),
     )
@@ -42,9 +43,7 @@ pub fn hook_list_expedients(
     Some(
         database
             .as_mut()?
-            .hook_list_expedients(options, move |expedients| {
-                js_callback.call(&window, expedients)
-            }),
+            .hook_list_expedients(options, move |page| js_callback.call(&window, page)),
     )
 }
 
@@ -60,17 +59,25 @@ pub fn hook_list_orders(
     Some(
         database
             .as_mut()?
-            .hook_list_orders(options, move |expedients| {
-                js_callback.call(&window, expedients)
-            }),
+            .hook_list_orders(options, move |page| js_callback.call(&window, page)),
     )
 }
 
 #[tauri::command]
-pub fn hook_list_users(
+pub fn fetch_orders_page(
+    state: tauri::State<ApiState>,
+    hook_id: HookId,
+    cursor: Option<ListOrdersCursor>,
+) {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_ref() {
+        database.fetch_orders_page(hook_id, cursor);
+    }
+}
+
+#[tauri::command]
+pub fn hook_list_saved_searches(
     state: tauri::State<ApiState>,
     window: tauri::Window,
-    filter: String,
     js_callback: JsCallback,
 ) -> Option<HookId> {
     let mut database = state.database_mutex.lock().unwrap();
@@ -78,35 +85,31 @@ pub fn hook_list_users(
     Some(
         database
             .as_mut()?
-            .hook_list_users(filter, move |expedients| {
-                js_callback.call(&window, expedients)
-            }),
+            .hook_list_saved_searches(move |names| js_callback.call(&window, names)),
     )
 }
 
 #[tauri::command]
-pub fn hook_list_models(
+pub fn hook_saved_search(
     state: tauri::State<ApiState>,
     window: tauri::Window,
-    filter: String,
+    name: String,
     js_callback: JsCallback,
 ) -> Option<HookId> {
     let mut database = state.database_mutex.lock().unwrap();
 
-    Some(
-        database
-            .as_mut()?
-            .hook_list_models(filter, move |expedients| {
-                js_callback.call(&window, expedients)
-            }),
-    )
+    database
+        .as_mut()?
+        .hook_saved_search(&name, move |page| js_callback.call(&window, page))
+        .ok()
 }
 
 #[tauri::command]
-pub fn hook_list_vins(
+pub fn hook_smart_search(
     state: tauri::State<ApiState>,
     window: tauri::Window,
-    filter: String,
+    query: String,
+    max_list_len: usize,
     js_callback: JsCallback,
 ) -> Option<HookId> {
     let mut database = state.database_mutex.lock().unwrap();
@@ -114,17 +117,17 @@ pub fn hook_list_vins(
     Some(
         database
             .as_mut()?
-            .hook_list_vins(filter, move |expedients| {
-                js_callback.call(&window, expedients)
+            .hook_smart_search(query, max_list_len, move |page| {
+                js_callback.call(&window, page)
             }),
     )
 }
 
 #[tauri::command]
-pub fn hook_list_license_plates(
+pub fn hook_duplicates(
     state: tauri::State<ApiState>,
     window: tauri::Window,
-    filter: String,
+    options: DuplicatesHookOptions,
     js_callback: JsCallback,
 ) -> Option<HookId> {
     let mut database = state.database_mutex.lock().unwrap();
@@ -132,17 +135,15 @@ pub fn hook_list_license_plates(
     Some(
         database
             .as_mut()?
-            .hook_list_license_plates(filter, move |expedients| {
-                js_callback.call(&window, expedients)
-            }),
+            .hook_duplicates(options, move |clusters| js_callback.call(&window, clusters)),
     )
 }
 
 #[tauri::command]
-pub fn hook_list_order_titles(
+pub fn hook_suggestions(
     state: tauri::State<ApiState>,
     window: tauri::Window,
-    filter: String,
+    options: SuggestionsHookOptions,
     js_callback: JsCallback,
 ) -> Option<HookId> {
     let mut database = state.database_mutex.lock().unwrap();
@@ -150,12 +151,19 @@ pub fn hook_list_order_titles(
     Some(
         database
             .as_mut()?
-            .hook_list_order_titles(filter, move |expedients| {
-                js_callback.call(&window, expedients)
+            .hook_suggestions(options, move |suggestions| {
+                js_callback.call(&window, suggestions)
             }),
     )
 }
 
+#[tauri::command]
+pub fn set_dispatch_window(state: tauri::State<ApiState>, millis: u64) {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
+        database.set_dispatch_window(std::time::Duration::from_millis(millis));
+    }
+}
+
 #[tauri::command]
 pub fn release_hook(state: tauri::State<ApiState>, hook_id: HookId) {
     if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
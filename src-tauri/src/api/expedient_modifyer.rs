@@ -1,18 +1,39 @@
 use super::ApiState;
-use crate::{chunked_database::Uid, expedient_database::*};
+use crate::{chunked_database::Uid, error::ErrorKind, expedient_database::*};
 
+/// Returns the id of the new expedient, `None` if it was rejected
 #[tauri::command]
 pub async fn create_expedient(
     state: tauri::State<'_, ApiState>,
     expedient: Expedient,
+    mode: Option<ValidationMode>,
 ) -> Result<Option<Uid>, ()> {
     if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
-        Ok(Some(database.create_expedient(expedient)))
+        Ok(database
+            .create_validated_expedient(expedient, mode.unwrap_or_default())
+            .id)
     } else {
         Ok(None)
     }
 }
 
+/// Returns the problems found on the fields of the expedient, `id` is `None` for a new expedient
+#[tauri::command]
+pub fn validate_expedient(
+    state: tauri::State<ApiState>,
+    id: Option<Uid>,
+    expedient: Expedient,
+) -> Option<ValidationReport> {
+    Some(
+        state
+            .database_mutex
+            .lock()
+            .unwrap()
+            .as_ref()?
+            .validate_expedient(id, &expedient),
+    )
+}
+
 #[tauri::command]
 pub fn delete_expedient(state: tauri::State<ApiState>, id: Uid) {
     if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
@@ -20,16 +41,158 @@ pub fn delete_expedient(state: tauri::State<ApiState>, id: Uid) {
     }
 }
 
+/// Returns the problems found on the fields of the expedient, its id is `None` if it was rejected.
+/// Fails with `Conflict` if the expedient is no longer on `revision`.
+/// Without a revision the expedient is overwritten whatever its revision is.
 #[tauri::command]
 pub async fn update_expedient(
     state: tauri::State<'_, ApiState>,
     id: Uid,
+    revision: Option<u32>,
     expedient: Expedient,
-) -> Result<(), ()> {
+    mode: Option<ValidationMode>,
+) -> crate::error::Result<ValidatedWrite> {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
+        database.update_validated_expedient(id, revision, expedient, mode.unwrap_or_default())
+    } else {
+        ErrorKind::NotFound.into()
+    }
+}
+
+/// Applies the patches to the expedient on `revision`, returns its new revision and
+/// the problems found on its fields. Fails with `Conflict` if it's no longer on `revision`.
+#[tauri::command]
+pub fn patch_expedient(
+    state: tauri::State<ApiState>,
+    id: Uid,
+    revision: u32,
+    patches: Vec<ExpedientPatch>,
+    mode: Option<ValidationMode>,
+) -> crate::error::Result<ValidatedWrite> {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
+        database.patch_expedient(id, revision, patches, mode.unwrap_or_default())
+    } else {
+        ErrorKind::NotFound.into()
+    }
+}
+
+/// Applies the operation to every order of the target, returns the amount of changed expedients
+#[tauri::command]
+pub fn bulk_edit(
+    state: tauri::State<ApiState>,
+    target: BulkTarget,
+    operation: BulkOperation,
+) -> crate::error::Result<usize> {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
+        database.bulk_edit(target, operation)
+    } else {
+        ErrorKind::NotFound.into()
+    }
+}
+
+/// Writes all the operations or none, returns the write of every created and updated expedient
+#[tauri::command]
+pub fn commit_transaction(
+    state: tauri::State<ApiState>,
+    transaction: Transaction,
+) -> crate::error::Result<Vec<ValidatedWrite>> {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
+        database.commit(transaction)
+    } else {
+        ErrorKind::NotFound.into()
+    }
+}
+
+/// Moves the order to another expedient, returns its index on it
+#[tauri::command]
+pub fn move_order(
+    state: tauri::State<ApiState>,
+    from: Uid,
+    order_index: usize,
+    to: Uid,
+) -> crate::error::Result<usize> {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
+        database.move_order(from, order_index, to)
+    } else {
+        ErrorKind::NotFound.into()
+    }
+}
+
+/// Moves the orders to a new expedient with another vehicle, returns its id
+#[tauri::command]
+pub fn split_expedient(
+    state: tauri::State<ApiState>,
+    id: Uid,
+    split: ExpedientSplit,
+) -> crate::error::Result<Uid> {
     if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
-        database.update_expedient(id, expedient);
+        database.split_expedient(id, split)
+    } else {
+        ErrorKind::NotFound.into()
+    }
+}
+
+#[tauri::command]
+pub fn linked_expedients(state: tauri::State<ApiState>, id: Uid) -> Vec<Uid> {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_ref() {
+        database.linked_expedients(id)
+    } else {
+        vec![]
+    }
+}
+
+/// Reverts the last change of the database, returns the expedients changed by it
+#[tauri::command]
+pub fn undo(state: tauri::State<ApiState>) -> Option<Vec<Uid>> {
+    state.database_mutex.lock().unwrap().as_mut()?.undo()
+}
+
+/// Does again the last undone change, returns the expedients changed by it
+#[tauri::command]
+pub fn redo(state: tauri::State<ApiState>) -> Option<Vec<Uid>> {
+    state.database_mutex.lock().unwrap().as_mut()?.redo()
+}
+
+#[tauri::command]
+pub fn expedient_revision(state: tauri::State<ApiState>, id: Uid) -> Option<u32> {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_ref() {
+        database.expedient_revision(id)
+    } else {
+        None
+    }
+}
+
+/// Merges the changes made from `base`, read on an old revision, into the stored expedient
+#[tauri::command]
+pub fn merge_expedient_revision(
+    state: tauri::State<ApiState>,
+    id: Uid,
+    base: Expedient,
+    expedient: Expedient,
+) -> crate::error::Result<RevisionMerge> {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_ref() {
+        database.merge_expedient_revision(id, &base, &expedient)
+    } else {
+        ErrorKind::NotFound.into()
+    }
+}
+
+#[tauri::command]
+pub fn merge_expedients(state: tauri::State<ApiState>, ids: Vec<Uid>) -> crate::error::Result<Uid> {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
+        database.merge_expedients(&ids)
+    } else {
+        ErrorKind::NotFound.into()
+    }
+}
+
+#[tauri::command]
+pub fn merged_expedient(state: tauri::State<ApiState>, id: Uid) -> Option<Uid> {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
+        database.merged_into(id)
+    } else {
+        None
     }
-    Ok(())
 }
 
 #[tauri::command]
@@ -41,6 +204,56 @@ pub fn read_expedient(state: tauri::State<ApiState>, id: Uid) -> Option<Expedien
     }
 }
 
+#[tauri::command]
+pub fn suggest_expedient_fields(
+    state: tauri::State<ApiState>,
+    expedient: Expedient,
+) -> Vec<FieldSuggestion> {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
+        database.suggest_fields(&expedient)
+    } else {
+        vec![]
+    }
+}
+
+#[tauri::command]
+pub fn explain_similarity(
+    state: tauri::State<ApiState>,
+    id: Uid,
+    expedient: Expedient,
+) -> Option<SimilarityBreakdown> {
+    state
+        .database_mutex
+        .lock()
+        .unwrap()
+        .as_ref()?
+        .explain_similarity(id, &expedient)
+}
+
+#[tauri::command]
+pub fn similarity_weights(state: tauri::State<ApiState>) -> Option<SimilarityWeights> {
+    Some(
+        state
+            .database_mutex
+            .lock()
+            .unwrap()
+            .as_ref()?
+            .similarity_weights(),
+    )
+}
+
+#[tauri::command]
+pub fn set_similarity_weights(
+    state: tauri::State<ApiState>,
+    weights: SimilarityWeights,
+) -> crate::error::Result<()> {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
+        database.set_similarity_weights(weights)
+    } else {
+        ErrorKind::NotFound.into()
+    }
+}
+
 #[tauri::command]
 pub fn count_expedients(state: tauri::State<ApiState>) -> usize {
     if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
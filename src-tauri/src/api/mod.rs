@@ -1,10 +1,14 @@
 mod expedient_hooks;
 mod expedient_modifyer;
+mod saved_searches;
 mod statistics;
+mod vin;
 
 pub use expedient_hooks::*;
 pub use expedient_modifyer::*;
+pub use saved_searches::*;
 pub use statistics::*;
+pub use vin::*;
 
 pub use crate::expedient_database::*;
 use crate::{
@@ -0,0 +1,33 @@
+use super::*;
+
+#[tauri::command]
+pub fn save_search(
+    state: tauri::State<ApiState>,
+    name: String,
+    options: ListOrdersHookOptions,
+) -> Result<()> {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
+        database.save_search(name, options)
+    } else {
+        ErrorKind::NotFound.into()
+    }
+}
+
+#[tauri::command]
+pub fn delete_search(state: tauri::State<ApiState>, name: String) -> Result<()> {
+    if let Some(database) = state.database_mutex.lock().unwrap().as_mut() {
+        database.delete_search(&name)
+    } else {
+        ErrorKind::NotFound.into()
+    }
+}
+
+#[tauri::command]
+pub fn read_search(state: tauri::State<ApiState>, name: String) -> Option<ListOrdersHookOptions> {
+    state
+        .database_mutex
+        .lock()
+        .unwrap()
+        .as_ref()?
+        .read_search(&name)
+}
@@ -0,0 +1,6 @@
+use crate::vin::{self, VinError, VinInfo};
+
+#[tauri::command]
+pub fn decode_vin(vin: String) -> Result<VinInfo, VinError> {
+    vin::decode(&vin)
+}
@@ -7,6 +7,10 @@ use std::path::PathBuf;
 
 pub trait Item: Serialize + DeserializeOwned + Clone + Sync + Send {
     fn date(&self) -> i64;
+    /// State of each part of the item, the database records when they change
+    fn part_states(&self) -> Vec<u8> {
+        vec![]
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -51,7 +55,7 @@ impl<T: Item> Chunk<T> {
         Database::<DataType<T>>::rollback_info(path)
     }
 
-    pub fn pop_oldest(&mut self) -> Option<T> {
+    pub fn pop_oldest(&mut self) -> Option<(Id, T)> {
         let mut oldest_id = None;
         let mut oldest_date = i64::MAX;
 
@@ -63,7 +67,8 @@ impl<T: Item> Chunk<T> {
             }
         }
 
-        self.database.data.items.take(oldest_id?)
+        let oldest_id = oldest_id?;
+        Some((oldest_id, self.database.data.items.take(oldest_id)?))
     }
 
     pub fn save(&mut self) -> Result<()> {
@@ -85,6 +90,9 @@ impl<T: Item> Chunk<T> {
     pub fn iter(&self) -> IdMapIter<'_, T> {
         self.database.data.items.iter()
     }
+    pub fn iter_partitions(&self, count: usize) -> Vec<IdMapIter<'_, T>> {
+        self.database.data.items.iter_partitions(count)
+    }
     pub fn push(&mut self, data: T) -> Id {
         self.modifyed = true;
         self.database.data.items.push(data)
@@ -158,8 +166,8 @@ mod test {
     fn pop_oldest() {
         let tempdir = TempDir::new();
         let mut chunk = Chunk::<Data>::create(&tempdir.path).unwrap();
-        chunk.database.data.items.push(Data(123));
-        assert_eq!(chunk.pop_oldest(), Some(Data(123)));
+        let id = chunk.database.data.items.push(Data(123));
+        assert_eq!(chunk.pop_oldest(), Some((id, Data(123))));
         assert_eq!(chunk.pop_oldest(), None);
         assert_eq!(chunk.pop_oldest(), None);
     }
@@ -1,12 +1,13 @@
 mod chunk;
 
 pub use crate::collections::*;
+use crate::database::Database;
 pub use crate::database::RollbackDateInfo;
 use crate::error::*;
 pub use chunk::Item;
 use chunk::*;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{collections::BTreeMap, ops::RangeInclusive, path::PathBuf};
 
 /// Data is composed of items, each item have a 'date' associated
 /// and is stored in on of the two interal databases in relation of that date
@@ -15,8 +16,12 @@ use std::path::PathBuf;
 /// This way, you can skip scaning very old data.
 ///
 /// In the ancient database are stored all the data considered old.
+///
+/// The last time each item was modified is also stored, sorted by date,
+/// its revision, the amount of times it was updated, the items linked to it
+/// and when the state of each of its parts last changed.
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Uid {
     DYNAMIC(chunk::Id),
     ANCIENT(chunk::Id),
@@ -36,13 +41,39 @@ pub struct ChunkedDatabase<T: Item + Send + Sync> {
     dynamic: Chunk<T>,
     ancient: Chunk<T>,
     max_dynamic_len: usize,
+    last_modified: Database<DateMap<Uid>>,
+    last_modified_changed: bool,
+    /// Items never updated are not stored, their revision is 0
+    revisions: Database<BTreeMap<Uid, u32>>,
+    revisions_changed: bool,
+    /// Links go both ways, items without links are not stored
+    links: Database<BTreeMap<Uid, Vec<Uid>>>,
+    links_changed: bool,
+    /// Deleted item -> item it was merged into
+    merged: Database<BTreeMap<Uid, Uid>>,
+    merged_changed: bool,
+    /// Date of the last state change of each part, `None` if it didn't change since it was created
+    state_changes: Database<BTreeMap<Uid, Vec<Option<UtcDate>>>>,
+    state_changes_changed: bool,
 }
 
 impl<T: Item + Send + Sync> ChunkedDatabase<T> {
     pub fn open(path: &PathBuf, max_dynamic_len: usize) -> Result<Self> {
+        let dynamic = Chunk::open(&path.join("dynamic"))?;
+        let ancient = Chunk::open(&path.join("ancient"))?;
         Ok(Self {
-            dynamic: Chunk::open(&path.join("dynamic"))?,
-            ancient: Chunk::open(&path.join("ancient"))?,
+            last_modified: Self::open_last_modified(path, &dynamic, &ancient)?,
+            last_modified_changed: false,
+            revisions: Database::open_or_create(&path.join("revisions"))?,
+            revisions_changed: false,
+            links: Database::open_or_create(&path.join("links"))?,
+            links_changed: false,
+            merged: Database::open_or_create(&path.join("merged"))?,
+            merged_changed: false,
+            state_changes: Database::open_or_create(&path.join("state_changes"))?,
+            state_changes_changed: false,
+            dynamic,
+            ancient,
             max_dynamic_len,
         })
     }
@@ -52,15 +83,99 @@ impl<T: Item + Send + Sync> ChunkedDatabase<T> {
             dynamic: Chunk::create(&path.join("dynamic"))?,
             ancient: Chunk::create(&path.join("ancient"))?,
             max_dynamic_len,
+            last_modified: Database::create(&path.join("last_modified"))?,
+            last_modified_changed: true,
+            revisions: Database::create(&path.join("revisions"))?,
+            revisions_changed: true,
+            links: Database::create(&path.join("links"))?,
+            links_changed: true,
+            merged: Database::create(&path.join("merged"))?,
+            merged_changed: true,
+            state_changes: Database::create(&path.join("state_changes"))?,
+            state_changes_changed: true,
         })
     }
 
+    /// The chunks are rolled back to their last noncorrupted backup.
+    /// The other files can be newer than them, so they are rebuilt from the rolled back items:
+    /// modification dates are the dates of the items, revisions start again
+    /// only the links between existing items are kept and only the merges into existing items,
+    /// and the state changes are forgotten.
     pub fn rollback(path: &PathBuf, max_dynamic_len: usize) -> Result<Self> {
-        Ok(Self {
-            dynamic: Chunk::rollback(&path.join("dynamic"))?,
-            ancient: Chunk::rollback(&path.join("ancient"))?,
+        let dynamic = Chunk::rollback(&path.join("dynamic"))?;
+        let ancient = Chunk::rollback(&path.join("ancient"))?;
+        let mut last_modified = Database::open_or_create(&path.join("last_modified"))?;
+        last_modified.data = Self::item_dates(&dynamic, &ancient);
+        let mut revisions =
+            Database::<BTreeMap<Uid, u32>>::open_or_create(&path.join("revisions"))?;
+        revisions.data.clear();
+        let links = Database::open_or_create(&path.join("links"))?;
+        let merged = Database::open_or_create(&path.join("merged"))?;
+        let mut state_changes = Database::<BTreeMap<Uid, Vec<Option<UtcDate>>>>::open_or_create(
+            &path.join("state_changes"),
+        )?;
+        state_changes.data.clear();
+
+        let mut database = Self {
+            last_modified,
+            last_modified_changed: true,
+            revisions,
+            revisions_changed: true,
+            links,
+            links_changed: true,
+            merged,
+            merged_changed: true,
+            state_changes,
+            state_changes_changed: true,
+            dynamic,
+            ancient,
             max_dynamic_len,
-        })
+        };
+        let links = std::mem::take(&mut database.links.data);
+        database.links.data = links
+            .into_iter()
+            .filter(|(id, _)| database.read(*id).is_some())
+            .map(|(id, links)| {
+                let links: Vec<_> = links
+                    .into_iter()
+                    .filter(|link| database.read(*link).is_some())
+                    .collect();
+                (id, links)
+            })
+            .filter(|(_, links)| !links.is_empty())
+            .collect();
+        let merged = std::mem::take(&mut database.merged.data);
+        database.merged.data = merged
+            .into_iter()
+            .filter(|(id, into)| database.read(*id).is_none() && database.read(*into).is_some())
+            .collect();
+        Ok(database)
+    }
+
+    /// Databases created before the modification dates were stored
+    /// use the date of the items as their last modification.
+    fn open_last_modified(
+        path: &PathBuf,
+        dynamic: &Chunk<T>,
+        ancient: &Chunk<T>,
+    ) -> Result<Database<DateMap<Uid>>> {
+        let mut last_modified =
+            Database::<DateMap<Uid>>::open_or_create(&path.join("last_modified"))?;
+        if last_modified.data.len() == 0 {
+            last_modified.data = Self::item_dates(dynamic, ancient);
+        }
+        Ok(last_modified)
+    }
+
+    fn item_dates(dynamic: &Chunk<T>, ancient: &Chunk<T>) -> DateMap<Uid> {
+        let mut dates = DateMap::default();
+        for item in dynamic.iter() {
+            dates.insert(Uid::DYNAMIC(item.id), UtcDate::from_hash(item.data.date()));
+        }
+        for item in ancient.iter() {
+            dates.insert(Uid::ANCIENT(item.id), UtcDate::from_hash(item.data.date()));
+        }
+        dates
     }
 
     pub fn rollback_info(path: &PathBuf) -> Result<RollbackDateInfo> {
@@ -86,6 +201,29 @@ impl<T: Item + Send + Sync> ChunkedDatabase<T> {
         iter.map(|item| (Uid::ANCIENT(item.id), item.data))
     }
 
+    /// Splits the dynamic items in `count` parts, to scan them in parallel
+    pub fn iter_partitions<'a>(
+        &'a self,
+        count: usize,
+    ) -> Vec<impl Iterator<Item = (Uid, &'a T)> + Send + 'a> {
+        self.dynamic
+            .iter_partitions(count)
+            .into_iter()
+            .map(|iter| iter.map(|item| (Uid::DYNAMIC(item.id), item.data)))
+            .collect()
+    }
+
+    pub fn iter_ancient_partitions<'a>(
+        &'a self,
+        count: usize,
+    ) -> Vec<impl Iterator<Item = (Uid, &'a T)> + Send + 'a> {
+        self.ancient
+            .iter_partitions(count)
+            .into_iter()
+            .map(|iter| iter.map(|item| (Uid::ANCIENT(item.id), item.data)))
+            .collect()
+    }
+
     pub fn iter_all<'a>(&'a self) -> impl Iterator<Item = (Uid, &'a T)> + 'a {
         self.iter().chain(self.iter_ancient())
     }
@@ -95,15 +233,129 @@ impl<T: Item + Send + Sync> ChunkedDatabase<T> {
             Uid::DYNAMIC(id) => self.dynamic.delete(id),
             Uid::ANCIENT(id) => self.ancient.delete(id),
         }
+        self.last_modified.data.remove(&id);
+        self.last_modified_changed = true;
+        if self.revisions.data.remove(&id).is_some() {
+            self.revisions_changed = true;
+        }
+        for linked_id in self.links.data.remove(&id).unwrap_or_default() {
+            self.unlink(linked_id, id);
+            self.links_changed = true;
+        }
+        if self.state_changes.data.remove(&id).is_some() {
+            self.state_changes_changed = true;
+        }
     }
     pub fn push(&mut self, item: T) -> Uid {
-        Uid::DYNAMIC(self.dynamic.push(item))
+        let id = Uid::DYNAMIC(self.dynamic.push(item));
+        self.last_modified.data.insert(id, UtcDate::now());
+        self.last_modified_changed = true;
+        id
     }
     pub fn update(&mut self, id: Uid, item: T) {
+        let states_before = self.read(id).map(|before| before.part_states());
+        let states = item.part_states();
         match id {
             Uid::DYNAMIC(id) => self.dynamic.update(id, item),
             Uid::ANCIENT(id) => self.ancient.update(id, item),
         }
+        if self.read(id).is_some() {
+            self.last_modified.data.insert(id, UtcDate::now());
+            self.last_modified_changed = true;
+            *self.revisions.data.entry(id).or_default() += 1;
+            self.revisions_changed = true;
+            self.record_state_changes(id, &states_before.unwrap_or_default(), &states);
+        }
+    }
+    /// Parts with another state than the part with the same index before are changed now
+    fn record_state_changes(&mut self, id: Uid, before: &[u8], after: &[u8]) {
+        let now = UtcDate::now();
+        let changes = self.state_changes.data.remove(&id).unwrap_or_default();
+        let changes: Vec<_> = after
+            .iter()
+            .enumerate()
+            .map(|(index, state)| {
+                if before.get(index) == Some(state) {
+                    changes.get(index).copied().flatten()
+                } else {
+                    Some(now)
+                }
+            })
+            .collect();
+        if changes.iter().any(Option::is_some) {
+            self.state_changes.data.insert(id, changes);
+        }
+        self.state_changes_changed = true;
+    }
+    /// Last time the state of the part changed, `None` if it didn't change since it was created
+    pub fn state_changed(&self, id: Uid, part_index: usize) -> Option<UtcDate> {
+        *self.state_changes.data.get(&id)?.get(part_index)?
+    }
+    /// Amount of times the item was updated, `None` if it doesn't exist
+    pub fn revision(&self, id: Uid) -> Option<u32> {
+        self.read(id)?;
+        Some(self.revisions.data.get(&id).copied().unwrap_or_default())
+    }
+    /// Links both items, they must exist
+    pub fn link(&mut self, a: Uid, b: Uid) {
+        if a == b || self.read(a).is_none() || self.read(b).is_none() {
+            return;
+        }
+        for (id, linked_id) in [(a, b), (b, a)] {
+            let links = self.links.data.entry(id).or_default();
+            if !links.contains(&linked_id) {
+                links.push(linked_id);
+                self.links_changed = true;
+            }
+        }
+    }
+    /// Records that the deleted item `id` was merged into `into`
+    pub fn record_merge(&mut self, id: Uid, into: Uid) {
+        self.merged.data.insert(id, into);
+        self.merged_changed = true;
+    }
+    pub fn forget_merge(&mut self, id: Uid) {
+        if self.merged.data.remove(&id).is_some() {
+            self.merged_changed = true;
+        }
+    }
+    /// Item that has now the data of the deleted item, following merges of merged items
+    pub fn merged_into(&self, id: Uid) -> Option<Uid> {
+        let mut into = *self.merged.data.get(&id)?;
+        // Bounded, in case of a cycle
+        for _ in 0..self.merged.data.len() {
+            match self.merged.data.get(&into) {
+                Some(next) => into = *next,
+                None => break,
+            }
+        }
+        Some(into)
+    }
+    /// Removes the link between both items
+    pub fn unlink(&mut self, a: Uid, b: Uid) {
+        for (id, linked_id) in [(a, b), (b, a)] {
+            if let Some(links) = self.links.data.get_mut(&id) {
+                let len = links.len();
+                links.retain(|other| *other != linked_id);
+                self.links_changed |= links.len() != len;
+                if links.is_empty() {
+                    self.links.data.remove(&id);
+                }
+            }
+        }
+    }
+    pub fn links(&self, id: Uid) -> &[Uid] {
+        self.links
+            .data
+            .get(&id)
+            .map_or(&[], |links| links.as_slice())
+    }
+    pub fn last_modified(&self, id: Uid) -> Option<UtcDate> {
+        self.last_modified.data.date(&id)
+    }
+    /// Items modified inside the range of `date_hash`, from the oldest modification to the newest
+    pub fn modified_between(&self, range: RangeInclusive<i64>) -> impl Iterator<Item = Uid> + '_ {
+        self.last_modified.data.range(range).copied()
     }
     pub fn read(&self, id: Uid) -> Option<&T> {
         match id {
@@ -115,33 +367,95 @@ impl<T: Item + Send + Sync> ChunkedDatabase<T> {
         self.dynamic.len() + self.ancient.len()
     }
 
-    /// Moves items from the dynamic chunk to the ancient chunk to satisfy 'max_dynamic_len'
-    fn move_old_items(&mut self) {
-        if self.dynamic.len() > self.max_dynamic_len {
+    pub fn dynamic_len(&self) -> usize {
+        self.dynamic.len()
+    }
+
+    pub fn ancient_len(&self) -> usize {
+        self.ancient.len()
+    }
+
+    /// Moves items from the dynamic chunk to the ancient chunk to satisfy 'max_dynamic_len'.
+    /// Returns the amount of moved items.
+    fn move_old_items(&mut self) -> usize {
+        let moved_items = self.dynamic.len().saturating_sub(self.max_dynamic_len);
+        if moved_items > 0 {
+            let mut moved_ids = BTreeMap::new();
             println!(
                 "Moving {} expedient to ancient database",
                 self.dynamic.len() - self.max_dynamic_len
             );
             while self.dynamic.len() > self.max_dynamic_len {
-                self.ancient.push(
-                    self.dynamic
-                        .pop_oldest()
-                        .expect("Dynamic len is > 0 but pop_oldest didn't find any"),
-                );
+                let (old_id, item) = self
+                    .dynamic
+                    .pop_oldest()
+                    .expect("Dynamic len is > 0 but pop_oldest didn't find any");
+                let new_id = Uid::ANCIENT(self.ancient.push(item));
+                if let Some(date) = self.last_modified.data.remove(&Uid::DYNAMIC(old_id)) {
+                    self.last_modified.data.insert(new_id, date);
+                }
+                if let Some(revision) = self.revisions.data.remove(&Uid::DYNAMIC(old_id)) {
+                    self.revisions.data.insert(new_id, revision);
+                }
+                if let Some(changes) = self.state_changes.data.remove(&Uid::DYNAMIC(old_id)) {
+                    self.state_changes.data.insert(new_id, changes);
+                }
+                moved_ids.insert(Uid::DYNAMIC(old_id), new_id);
             }
+            self.last_modified_changed = true;
+            self.revisions_changed = true;
+            self.state_changes_changed = true;
+            self.move_links(&moved_ids);
+            for into in self.merged.data.values_mut() {
+                *into = *moved_ids.get(into).unwrap_or(into);
+            }
+            self.merged_changed = true;
             println!(
                 "Dinamic database: {}  Ancient database: {}",
                 self.dynamic.len(),
                 self.ancient.len()
             );
         }
+        moved_items
     }
 
-    pub fn save(&mut self) -> Result<()> {
-        self.move_old_items();
+    /// Replaces the old ids of the moved items on the links
+    fn move_links(&mut self, moved_ids: &BTreeMap<Uid, Uid>) {
+        let new_id = |id: &Uid| *moved_ids.get(id).unwrap_or(id);
+        let links = std::mem::take(&mut self.links.data);
+        self.links.data = links
+            .into_iter()
+            .map(|(id, links)| (new_id(&id), links.iter().map(new_id).collect()))
+            .collect();
+        self.links_changed = true;
+    }
+
+    /// Returns the amount of items moved to the ancient chunk, their `Uid` is no longer valid.
+    pub fn save(&mut self) -> Result<usize> {
+        let moved_items = self.move_old_items();
         self.dynamic.save()?;
         self.ancient.save()?;
-        Ok(())
+        if self.last_modified_changed {
+            self.last_modified.store()?;
+            self.last_modified_changed = false;
+        }
+        if self.revisions_changed {
+            self.revisions.store()?;
+            self.revisions_changed = false;
+        }
+        if self.links_changed {
+            self.links.store()?;
+            self.links_changed = false;
+        }
+        if self.merged_changed {
+            self.merged.store()?;
+            self.merged_changed = false;
+        }
+        if self.state_changes_changed {
+            self.state_changes.store()?;
+            self.state_changes_changed = false;
+        }
+        Ok(moved_items)
     }
 }
 
@@ -154,7 +468,7 @@ impl<T: Item + Send + Sync> Drop for ChunkedDatabase<T> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::test_utils::*;
+    use crate::{database::file, test_utils::*};
 
     #[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
     struct Data(i64);
@@ -164,6 +478,18 @@ mod test {
         }
     }
 
+    /// Item with a state for each part, its date is the amount of parts
+    #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+    struct Parts(Vec<u8>);
+    impl Item for Parts {
+        fn date(&self) -> i64 {
+            self.0.len() as i64
+        }
+        fn part_states(&self) -> Vec<u8> {
+            self.0.clone()
+        }
+    }
+
     #[test]
     fn push_len_and_read() {
         let tempdir = TempDir::new();
@@ -196,6 +522,152 @@ mod test {
         assert_eq!(2, db.iter_ancient().count());
     }
 
+    #[test]
+    fn keep_last_modified_of_moved_items() {
+        let tempdir = TempDir::new();
+        let start = UtcDate::now().date_hash();
+
+        let mut db = ChunkedDatabase::<Data>::create(&tempdir.path, 1).unwrap();
+        let id_old = db.push(Data(13));
+        let id_new = db.push(Data(54));
+        db.delete(id_new);
+        let id_new = db.push(Data(74));
+        db.move_old_items();
+
+        assert_eq!(None, db.last_modified(id_old));
+        let moved_ids: Vec<_> = db.iter_ancient().map(|(id, _)| id).collect();
+        assert_eq!(1, moved_ids.len());
+        assert!(db.last_modified(moved_ids[0]).unwrap().date_hash() >= start);
+        assert!(db.last_modified(id_new).is_some());
+
+        let mut modified: Vec<_> = db.modified_between(start..=i64::MAX).collect();
+        modified.sort_unstable();
+        assert_eq!(vec![id_new, moved_ids[0]], modified);
+    }
+
+    #[test]
+    fn record_state_changes_of_moved_items() {
+        let tempdir = TempDir::new();
+        let start = UtcDate::now().date_hash();
+
+        let mut db = ChunkedDatabase::<Parts>::create(&tempdir.path, 1).unwrap();
+        let id_old = db.push(Parts(vec![1, 2]));
+        assert_eq!(None, db.state_changed(id_old, 0));
+        db.update(id_old, Parts(vec![1, 3, 0]));
+        let id_new = db.push(Parts(vec![0, 0, 0, 0]));
+        db.move_old_items();
+
+        let moved_id = db.iter_ancient().next().unwrap().0;
+        assert_eq!(None, db.state_changed(moved_id, 0));
+        assert!(db.state_changed(moved_id, 1).unwrap().date_hash() >= start);
+        assert!(db.state_changed(moved_id, 2).is_some());
+        assert_eq!(None, db.state_changed(id_new, 0));
+    }
+
+    #[test]
+    fn count_revisions_of_moved_items() {
+        let tempdir = TempDir::new();
+
+        let mut db = ChunkedDatabase::<Data>::create(&tempdir.path, 1).unwrap();
+        let id_old = db.push(Data(13));
+        let id_new = db.push(Data(54));
+        assert_eq!(Some(0), db.revision(id_old));
+        db.update(id_old, Data(14));
+        db.update(id_old, Data(15));
+        db.update(id_new, Data(55));
+        db.move_old_items();
+
+        let moved_ids: Vec<_> = db.iter_ancient().map(|(id, _)| id).collect();
+        assert_eq!(Some(2), db.revision(moved_ids[0]));
+        assert_eq!(Some(1), db.revision(id_new));
+        db.delete(id_new);
+        assert_eq!(None, db.revision(id_new));
+    }
+
+    #[test]
+    fn keep_links_of_moved_and_deleted_items() {
+        let tempdir = TempDir::new();
+
+        let mut db = ChunkedDatabase::<Data>::create(&tempdir.path, 2).unwrap();
+        let id_old = db.push(Data(13));
+        let id_a = db.push(Data(54));
+        let id_b = db.push(Data(74));
+        db.link(id_old, id_a);
+        db.link(id_old, id_b);
+        db.link(id_a, id_old);
+        assert_eq!(&[id_a, id_b], db.links(id_old));
+        db.move_old_items();
+
+        let moved_ids: Vec<_> = db.iter_ancient().map(|(id, _)| id).collect();
+        assert_eq!(&[id_a, id_b], db.links(moved_ids[0]));
+        assert_eq!(&[moved_ids[0]], db.links(id_a));
+
+        db.delete(moved_ids[0]);
+        assert!(db.links(id_a).is_empty());
+        assert!(db.links(id_b).is_empty());
+    }
+
+    #[test]
+    fn follow_merges_of_moved_items() {
+        let tempdir = TempDir::new();
+
+        let mut db = ChunkedDatabase::<Data>::create(&tempdir.path, 2).unwrap();
+        let id_a = db.push(Data(13));
+        let id_b = db.push(Data(54));
+        let id_c = db.push(Data(74));
+        db.delete(id_c);
+        db.record_merge(id_c, id_b);
+        db.delete(id_b);
+        db.record_merge(id_b, id_a);
+        assert_eq!(Some(id_a), db.merged_into(id_c));
+        assert_eq!(None, db.merged_into(id_a));
+
+        db.push(Data(90));
+        db.push(Data(91));
+        db.move_old_items();
+        let moved_ids: Vec<_> = db.iter_ancient().map(|(id, _)| id).collect();
+        assert_eq!(Some(moved_ids[0]), db.merged_into(id_c));
+
+        db.forget_merge(id_b);
+        assert_eq!(Some(id_b), db.merged_into(id_c));
+    }
+
+    #[test]
+    fn rebuild_side_files_on_rollback() {
+        let tempdir = TempDir::new();
+        let (id_a, id_b) = {
+            let mut db = ChunkedDatabase::<Data>::create(&tempdir.path, 10).unwrap();
+            let id_a = db.push(Data(13));
+            let id_b = db.push(Data(54));
+            db.link(id_a, id_b);
+            db.save().unwrap();
+            sleep_for(1100);
+
+            let id_c = db.push(Data(74));
+            db.link(id_a, id_c);
+            db.update(id_a, Data(14));
+            db.save().unwrap();
+            (id_a, id_b)
+        };
+
+        // Corrupt the newest dynamic chunk, it goes back to the first save
+        let dynamic = tempdir.path.join("dynamic");
+        let newest = file::select_backup(&dynamic, |(path, _)| Some(path))
+            .unwrap()
+            .unwrap();
+        std::fs::write(newest, b"corrupted").unwrap();
+
+        let db = ChunkedDatabase::<Data>::rollback(&tempdir.path, 10).unwrap();
+        assert_eq!(2, db.len());
+        assert_eq!(Some(&Data(13)), db.read(id_a));
+        assert_eq!(Some(0), db.revision(id_a));
+        assert_eq!(&[id_b], db.links(id_a));
+        assert_eq!(
+            Some(13),
+            db.last_modified(id_a).map(|date| date.date_hash())
+        );
+    }
+
     #[test]
     fn move_old_items_on_drop() {
         let tempdir = TempDir::new();
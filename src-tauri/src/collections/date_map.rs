@@ -1,5 +1,11 @@
 use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::RangeInclusive,
+};
+
+const MS_ON_A_DAY: i64 = 1000 * 60 * 60 * 24;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub struct UtcDate {
@@ -7,6 +13,12 @@ pub struct UtcDate {
 }
 
 impl UtcDate {
+    pub fn now() -> Self {
+        Self {
+            timespan: Utc::now().timestamp_millis(),
+        }
+    }
+
     pub fn ymdh(year: i32, month: u32, day: u32, hour: u32) -> Self {
         let date = Utc.ymd(year, month, day).and_hms(hour, 0, 0);
         Self {
@@ -15,10 +27,22 @@ impl UtcDate {
     }
 
     pub fn day_hash(&self) -> i64 {
-        const MS_ON_A_DAY: i64 = 1000 * 60 * 60 * 24;
         self.timespan / MS_ON_A_DAY
     }
 
+    /// First instant of the day
+    pub fn day(&self) -> Self {
+        Self {
+            timespan: self.day_hash() * MS_ON_A_DAY,
+        }
+    }
+
+    /// First instant of the month
+    pub fn month(&self) -> Self {
+        let date = Utc.timestamp_millis_opt(self.timespan).unwrap();
+        Self::ymdh(date.year(), date.month(), 1, 0)
+    }
+
     pub fn date_hash(&self) -> i64 {
         self.timespan
     }
@@ -28,6 +52,106 @@ impl UtcDate {
     }
 }
 
+/// Limit of a `DateRange`, it can be relative to the moment when it's used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DateBound {
+    Date(UtcDate),
+    DaysAgo(u32),
+}
+
+impl DateBound {
+    pub fn date(&self) -> UtcDate {
+        match self {
+            DateBound::Date(date) => *date,
+            DateBound::DaysAgo(days) => {
+                UtcDate::from_hash(UtcDate::now().date_hash() - *days as i64 * MS_ON_A_DAY)
+            }
+        }
+    }
+}
+
+/// Dates between `from` and `to`, both included. A missing bound doesn't limit the range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DateRange {
+    pub from: Option<DateBound>,
+    pub to: Option<DateBound>,
+}
+
+impl DateRange {
+    pub fn is_unbounded(&self) -> bool {
+        self.from.is_none() && self.to.is_none()
+    }
+
+    /// The range moves with the current date
+    pub fn is_relative(&self) -> bool {
+        matches!(self.from, Some(DateBound::DaysAgo(_)))
+            || matches!(self.to, Some(DateBound::DaysAgo(_)))
+    }
+
+    /// Range of `date_hash`, relative bounds are resolved now
+    pub fn hashes(&self) -> RangeInclusive<i64> {
+        self.from.map_or(i64::MIN, |from| from.date().date_hash())
+            ..=self.to.map_or(i64::MAX, |to| to.date().date_hash())
+    }
+}
+
+/// Items sorted by date, to find the ones inside a range without checking all of them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DateMap<T: Ord + Clone> {
+    by_date: BTreeMap<i64, BTreeSet<T>>,
+    dates: BTreeMap<T, i64>,
+}
+
+impl<T: Ord + Clone> Default for DateMap<T> {
+    fn default() -> Self {
+        Self {
+            by_date: Default::default(),
+            dates: Default::default(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> DateMap<T> {
+    pub fn len(&self) -> usize {
+        self.dates.len()
+    }
+
+    /// Sets the date of the item, replacing the previous one
+    pub fn insert(&mut self, item: T, date: UtcDate) {
+        self.remove(&item);
+        self.by_date
+            .entry(date.date_hash())
+            .or_default()
+            .insert(item.clone());
+        self.dates.insert(item, date.date_hash());
+    }
+
+    pub fn remove(&mut self, item: &T) -> Option<UtcDate> {
+        let date = self.dates.remove(item)?;
+        if let Some(items) = self.by_date.get_mut(&date) {
+            items.remove(item);
+            if items.is_empty() {
+                self.by_date.remove(&date);
+            }
+        }
+        Some(UtcDate::from_hash(date))
+    }
+
+    pub fn date(&self, item: &T) -> Option<UtcDate> {
+        self.dates.get(item).map(|date| UtcDate::from_hash(*date))
+    }
+
+    /// Items with a date inside the range, from the oldest to the newest
+    pub fn range(&self, range: RangeInclusive<i64>) -> impl Iterator<Item = &T> {
+        // BTreeMap::range panics with an empty range
+        (!range.is_empty())
+            .then(|| self.by_date.range(range))
+            .into_iter()
+            .flatten()
+            .flat_map(|(_, items)| items.iter())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -50,6 +174,40 @@ mod test {
         assert_eq!(date_f, date_g);
     }
 
+    #[test]
+    fn date_map_range() {
+        let mut map = DateMap::default();
+        map.insert('a', UtcDate::ymdh(2010, 3, 4, 0));
+        map.insert('b', UtcDate::ymdh(2010, 3, 6, 0));
+        map.insert('c', UtcDate::ymdh(2010, 3, 5, 0));
+        map.insert('a', UtcDate::ymdh(2010, 3, 8, 0));
+        map.insert('d', UtcDate::ymdh(2010, 3, 5, 0));
+        assert_eq!(Some(UtcDate::ymdh(2010, 3, 5, 0)), map.remove(&'d'));
+
+        let range = DateRange {
+            from: Some(DateBound::Date(UtcDate::ymdh(2010, 3, 5, 0))),
+            to: Some(DateBound::Date(UtcDate::ymdh(2010, 3, 8, 0))),
+        };
+        assert_eq!(
+            vec!['c', 'b', 'a'],
+            map.range(range.hashes()).copied().collect::<Vec<_>>()
+        );
+        assert_eq!(3, map.len());
+        assert_eq!(Some(UtcDate::ymdh(2010, 3, 8, 0)), map.date(&'a'));
+    }
+
+    #[test]
+    fn relative_date_range() {
+        let last_week = DateRange {
+            from: Some(DateBound::DaysAgo(7)),
+            to: None,
+        };
+        let hashes = last_week.hashes();
+        assert!(hashes.contains(&UtcDate::now().date_hash()));
+        assert!(!hashes.contains(&UtcDate::ymdh(2010, 3, 5, 0).date_hash()));
+        assert!(DateRange::default().is_unbounded());
+    }
+
     #[test]
     fn ymd_hash_date_difference() {
         let date_a = UtcDate::ymdh(2010, 3, 4, 0).date_hash();
@@ -111,6 +111,19 @@ impl<T> IdMap<T> {
         }
     }
 
+    /// Splits the items in `count` consecutive parts that can be iterated independently
+    pub fn iter_partitions(&self, count: usize) -> Vec<IdMapIter<'_, T>> {
+        let partition_len = (self.data.len() + count.max(1) - 1) / count.max(1);
+        self.data
+            .chunks(partition_len.max(1))
+            .enumerate()
+            .map(|(partition, data)| IdMapIter {
+                data_iter: data.iter(),
+                index: partition * partition_len,
+            })
+            .collect()
+    }
+
     pub fn iter_mut(&mut self) -> IdMapIterMut<'_, T> {
         IdMapIterMut {
             data_iter: self.data.iter_mut(),
@@ -423,4 +436,22 @@ mod test {
         map.take(id_0);
         assert!(!map.exists(id_0));
     }
+
+    #[test]
+    fn iter_partitions_in_order() {
+        let mut map = IdMap::<i32>::default();
+        let ids: Vec<_> = (0..10).map(|data| map.push(data)).collect();
+        map.delete(ids[4]);
+
+        let partitions = map.iter_partitions(3);
+        assert_eq!(3, partitions.len());
+
+        let items: Vec<_> = partitions
+            .into_iter()
+            .flatten()
+            .map(|item| (item.id, *item.data))
+            .collect();
+        let expected: Vec<_> = map.iter().map(|item| (item.id, *item.data)).collect();
+        assert_eq!(expected, items);
+    }
 }
@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 
-mod file;
+pub(crate) mod file;
 mod time;
 
 pub use crate::error::{ErrorKind, Result};
@@ -68,6 +68,26 @@ where
         })
     }
 
+    /// Opens the database, or creates an empty one if it doesn't exist.
+    ///
+    ///# Errors
+    /// - `Collision`: When lock fails.
+    /// - `DataIsCorrupted`: When the last instance of database is corrupted.
+    pub fn open_or_create(path: &PathBuf) -> Result<Self> {
+        match Self::open(path) {
+            Err(error) if matches!(*error, ErrorKind::NotFound) => {
+                create_dir_all(path)?;
+                let lock = file::Lock::directory(path)?;
+                Ok(Self {
+                    lock,
+                    data: Default::default(),
+                    path: path.clone(),
+                })
+            }
+            result => result,
+        }
+    }
+
     /// Usualy, after a `rollback`, we might whant to see some
     /// information about the rollback date.
     ///
@@ -169,6 +189,19 @@ mod tests {
         assert_eq!("Err(NotFound)", format!("{:?}", error));
     }
 
+    #[test]
+    fn open_or_create_database() {
+        let tempdir = TempDir::new();
+        Database::<i32>::open_or_create(&tempdir.path).unwrap();
+        Database::<i32>::open_or_create(&tempdir.path.join("nested_folder")).unwrap();
+
+        let mut database = Database::<i32>::open_or_create(&tempdir.path).unwrap();
+        database.data = 7;
+        drop(database);
+        let database = Database::<i32>::open_or_create(&tempdir.path).unwrap();
+        assert_eq!(7, database.data);
+    }
+
     #[test]
     fn create_database_on_empty_dir() {
         let tempdir = TempDir::new();
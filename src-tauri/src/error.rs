@@ -11,6 +11,10 @@ pub enum ErrorKind {
     NotFound,
     DataIsCorrupted,
     Collision,
+    /// The item was modified after the revision that was read
+    Conflict,
+    /// The given values can't be used
+    InvalidInput,
     UnexpectedIoError(String),
 }
 
@@ -0,0 +1,114 @@
+use super::*;
+use std::collections::{btree_map::Entry, BTreeMap};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum BulkOperation {
+    SetOrderState(OrderState),
+    /// Reassigns the expedients of the orders to another customer
+    SetUser(String),
+    /// Adds a line at the end of the description of the orders
+    AppendNote(String),
+    /// Sets the model of the expedients of the orders, to fix its spelling
+    SetModel(String),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum BulkTarget {
+    /// Expedient and index of each order
+    Orders(Vec<(Uid, usize)>),
+    /// Every order listed by the options, not only the ones of a page
+    Query(ListOrdersHookOptions),
+}
+
+impl BulkOperation {
+    /// Returns false if the expedient is left as it was
+    fn apply(&self, expedient: &mut Expedient, order_index: usize) -> bool {
+        let order = &mut expedient.orders[order_index];
+        match self {
+            BulkOperation::SetOrderState(state) => {
+                std::mem::replace(&mut order.state, *state) != *state
+            }
+            BulkOperation::SetUser(user) => {
+                std::mem::replace(&mut expedient.user, user.clone()) != *user
+            }
+            BulkOperation::AppendNote(note) => {
+                let note = note.trim();
+                if note.is_empty() {
+                    return false;
+                }
+                if !order.description.trim().is_empty() {
+                    order.description.push('\n');
+                }
+                order.description.push_str(note);
+                true
+            }
+            BulkOperation::SetModel(model) => {
+                std::mem::replace(&mut expedient.model, model.clone()) != *model
+            }
+        }
+    }
+}
+
+impl<'a> ExpedientDatabase<'a> {
+    /// Applies the operation to every order of the target at once, it's undone at once.
+    /// Returns the amount of changed expedients.
+    pub fn bulk_edit(&mut self, target: BulkTarget, operation: BulkOperation) -> Result<usize> {
+        // Nothing is interrupted until all the orders are found
+        let mut mutations = vec![];
+        {
+            let database = self.database.read().unwrap();
+            let orders = match target {
+                BulkTarget::Orders(orders) => orders,
+                BulkTarget::Query(options) => Self::query_orders(options, &database),
+            };
+
+            let mut expedients = BTreeMap::<Uid, (Expedient, Vec<usize>)>::new();
+            for (id, order_index) in orders {
+                let (expedient, order_indices) = match expedients.entry(id) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => match database.read(id) {
+                        Some(expedient) => entry.insert((expedient.clone(), vec![])),
+                        None => return ErrorKind::NotFound.into(),
+                    },
+                };
+                if order_index >= expedient.orders.len() {
+                    return ErrorKind::NotFound.into();
+                }
+                if !order_indices.contains(&order_index) {
+                    order_indices.push(order_index);
+                }
+            }
+
+            for (id, (before, order_indices)) in expedients {
+                let mut after = before.clone();
+                let mut changed = false;
+                for order_index in order_indices {
+                    changed |= operation.apply(&mut after, order_index);
+                }
+                if changed {
+                    mutations.push(Mutation::new(id, Some(before), Some(after)));
+                }
+            }
+        }
+        if mutations.is_empty() {
+            return Ok(0);
+        }
+
+        self.interrupt_dispatch();
+        let mut changes = ChangeSet::default();
+        {
+            let mut database = self.database.write().unwrap();
+            for mutation in &mutations {
+                if let Some(after) = &mutation.after {
+                    database.update(mutation.id, after.clone());
+                    changes.updated.push(mutation.id);
+                }
+            }
+        }
+
+        let changed = mutations.len();
+        self.history.record(mutations);
+        self.dispatch_change(changes);
+        Ok(changed)
+    }
+}
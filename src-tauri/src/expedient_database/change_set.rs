@@ -0,0 +1,106 @@
+use crate::chunked_database::Uid;
+
+/// Expedients modified since the last time the hooks were dispatched.
+/// Hooks use it to update their last result instead of scanning all the database.
+#[derive(Debug, Clone, Default)]
+pub struct ChangeSet {
+    pub created: Vec<Uid>,
+    pub updated: Vec<Uid>,
+    pub deleted: Vec<Uid>,
+    /// The `Uid` of the expedients could have changed (ex: moved to the ancient chunk),
+    /// so hooks must scan all the database
+    pub rescan: bool,
+}
+
+impl ChangeSet {
+    pub fn created(id: Uid) -> Self {
+        Self {
+            created: vec![id],
+            ..Default::default()
+        }
+    }
+    pub fn updated(id: Uid) -> Self {
+        Self {
+            updated: vec![id],
+            ..Default::default()
+        }
+    }
+    pub fn deleted(id: Uid) -> Self {
+        Self {
+            deleted: vec![id],
+            ..Default::default()
+        }
+    }
+    pub fn rescan() -> Self {
+        Self {
+            rescan: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn append(&mut self, mut other: ChangeSet) {
+        self.created.append(&mut other.created);
+        self.updated.append(&mut other.updated);
+        self.deleted.append(&mut other.deleted);
+        self.rescan |= other.rescan;
+    }
+
+    pub fn len(&self) -> usize {
+        self.created.len() + self.updated.len() + self.deleted.len()
+    }
+
+    pub fn contains(&self, id: Uid) -> bool {
+        self.rescan
+            || self.created.contains(&id)
+            || self.updated.contains(&id)
+            || self.deleted.contains(&id)
+    }
+
+    /// Created or updated expedients that have not been deleted, without repetitions
+    pub fn changed(&self) -> Vec<Uid> {
+        let mut ids: Vec<_> = self
+            .created
+            .iter()
+            .chain(self.updated.iter())
+            .filter(|id| !self.deleted.contains(id))
+            .copied()
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::collections::Id;
+
+    fn uid(index: usize) -> Uid {
+        Uid::DYNAMIC(Id {
+            index,
+            identifier: index + 1,
+        })
+    }
+
+    #[test]
+    fn append_and_list_changed() {
+        let mut changes = ChangeSet::created(uid(0));
+        changes.append(ChangeSet::updated(uid(1)));
+        changes.append(ChangeSet::updated(uid(0)));
+        changes.append(ChangeSet::deleted(uid(1)));
+
+        assert_eq!(4, changes.len());
+        assert!(changes.contains(uid(1)));
+        assert!(!changes.contains(uid(2)));
+        assert_eq!(vec![uid(0)], changes.changed());
+    }
+
+    #[test]
+    fn rescan_contains_all() {
+        let mut changes = ChangeSet::default();
+        assert!(!changes.contains(uid(3)));
+        changes.append(ChangeSet::rescan());
+        assert!(changes.contains(uid(3)));
+    }
+}
@@ -0,0 +1,238 @@
+use super::*;
+use crate::{license_plate, observable::AsyncCallbackProcess, vin};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DuplicatesHookOptions {
+    /// Expedients of the same vehicle less similar than this are not considered duplicates
+    #[serde(default = "DuplicatesHookOptions::default_min_similarity")]
+    pub min_similarity: f32,
+}
+
+impl DuplicatesHookOptions {
+    fn default_min_similarity() -> f32 {
+        0.4
+    }
+}
+
+impl Default for DuplicatesHookOptions {
+    fn default() -> Self {
+        Self {
+            min_similarity: Self::default_min_similarity(),
+        }
+    }
+}
+
+/// Expedients that refer to the same vehicle, the newest first
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateCluster<'a> {
+    pub expedients: Vec<(Uid, &'a Expedient)>,
+    /// Lowest similarity between the expedients that joined the cluster
+    pub similarity: f32,
+}
+
+/// Union-find of the indices of the scanned expedients
+struct Clusters {
+    parents: Vec<usize>,
+    similarities: Vec<f32>,
+    /// VIN of the cluster, clusters with different VINs are never joined
+    vins: Vec<String>,
+}
+
+impl Clusters {
+    fn new(vins: Vec<String>) -> Self {
+        Self {
+            parents: (0..vins.len()).collect(),
+            similarities: vec![1.; vins.len()],
+            vins,
+        }
+    }
+
+    fn root(&mut self, mut index: usize) -> usize {
+        while self.parents[index] != index {
+            self.parents[index] = self.parents[self.parents[index]];
+            index = self.parents[index];
+        }
+        index
+    }
+
+    fn can_join(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.root(a), self.root(b));
+        self.vins[a].is_empty() || self.vins[b].is_empty() || self.vins[a] == self.vins[b]
+    }
+
+    fn join(&mut self, a: usize, b: usize, similarity: f32) {
+        let (a, b) = (self.root(a), self.root(b));
+        if a != b {
+            if self.vins[a].is_empty() {
+                self.vins[a] = std::mem::take(&mut self.vins[b]);
+            }
+            self.parents[b] = a;
+            self.similarities[a] = self.similarities[a]
+                .min(self.similarities[b])
+                .min(similarity);
+        }
+    }
+}
+
+/// Clusters the expedients with the same VIN, or with the same license plate and no different VIN.
+/// Expedients are compared with the first one with the same VIN or license plate.
+pub(super) fn find_duplicates<'b>(
+    mut expedients: Vec<(Uid, &'b Expedient)>,
+    options: &DuplicatesHookOptions,
+    weights: &SimilarityWeights,
+    process: &AsyncCallbackProcess,
+) -> Option<Vec<DuplicateCluster<'b>>> {
+    expedients.sort_unstable_by_key(|(id, _)| *id);
+    let mut clusters = Clusters::new(
+        expedients
+            .iter()
+            .map(|(_, expedient)| vin::normalize(&expedient.vin))
+            .collect(),
+    );
+    let mut by_vin = HashMap::<String, usize>::new();
+    let mut by_license_plate = HashMap::<String, usize>::new();
+
+    for (index, (_, expedient)) in expedients.iter().enumerate() {
+        if index % 1024 == 0 {
+            process.terminate_if_requested()?;
+        }
+        let vin = vin::normalize(&expedient.vin);
+        let license_plate = license_plate::normalize(&expedient.license_plate);
+
+        let mut join_with = |first: usize| {
+            if !clusters.can_join(first, index) {
+                return;
+            }
+            let similarity = expedient.similarity_of_filled(expedients[first].1, weights);
+            if similarity >= options.min_similarity {
+                clusters.join(first, index, similarity);
+            }
+        };
+
+        if !vin.is_empty() {
+            match by_vin.get(&vin) {
+                Some(first) => join_with(*first),
+                None => {
+                    by_vin.insert(vin.clone(), index);
+                }
+            }
+        }
+        if !license_plate.is_empty() {
+            match by_license_plate.get(&license_plate) {
+                Some(first) => join_with(*first),
+                None => {
+                    by_license_plate.insert(license_plate, index);
+                }
+            }
+        }
+    }
+
+    process.terminate_if_requested()?;
+
+    let mut members = HashMap::<usize, Vec<usize>>::new();
+    for index in 0..expedients.len() {
+        let root = clusters.root(index);
+        members.entry(root).or_default().push(index);
+    }
+
+    let mut list: Vec<_> = members
+        .into_iter()
+        .filter(|(_, indices)| indices.len() > 1)
+        .map(|(root, indices)| {
+            let mut cluster: Vec<_> = indices.into_iter().map(|index| expedients[index]).collect();
+            cluster.sort_unstable_by_key(|(id, expedient)| {
+                (-expedient.newest_date().date_hash(), *id)
+            });
+            DuplicateCluster {
+                expedients: cluster,
+                similarity: clusters.similarities[root],
+            }
+        })
+        .collect();
+    list.sort_unstable_by_key(|cluster| {
+        (
+            -cluster.expedients[0].1.newest_date().date_hash(),
+            cluster.expedients[0].0,
+        )
+    });
+    Some(list)
+}
+
+/// Fills the empty fields of `into` with the ones of `from`, and keeps the orders of both
+fn merge_expedient(into: &mut Expedient, from: &Expedient) {
+    for (field, other) in [
+        (&mut into.user, &from.user),
+        (&mut into.model, &from.model),
+        (&mut into.license_plate, &from.license_plate),
+        (&mut into.vin, &from.vin),
+    ] {
+        if field.trim().is_empty() {
+            *field = other.clone();
+        }
+    }
+
+    let description = from.description.trim();
+    if !description.is_empty() && !into.description.contains(description) {
+        if !into.description.trim().is_empty() {
+            into.description.push('\n');
+        }
+        into.description.push_str(description);
+    }
+
+    for order in &from.orders {
+        if !into.orders.contains(order) {
+            into.orders.push(order.clone());
+        }
+    }
+    into.orders.sort_by_key(|order| order.date.date_hash());
+
+    if from.date.date_hash() < into.date.date_hash() {
+        into.date = from.date;
+    }
+}
+
+impl<'a> ExpedientDatabase<'a> {
+    /// Merges the expedients into the first one and deletes the others.
+    /// Orders of all the expedients are kept, and the date of the oldest one.
+    /// Deleted expedients are recorded as merged into the first one, see `merged_into`.
+    pub fn merge_expedients(&mut self, ids: &[Uid]) -> Result<Uid> {
+        let (into_id, from_ids) = match ids.split_first() {
+            Some((into_id, from_ids)) => (*into_id, from_ids),
+            None => return ErrorKind::NotFound.into(),
+        };
+
+        let merged = {
+            let database = self.database.read().unwrap();
+            let mut merged = match database.read(into_id) {
+                Some(into) => into.clone(),
+                None => return ErrorKind::NotFound.into(),
+            };
+            for id in from_ids {
+                match database.read(*id) {
+                    Some(from) if *id != into_id => merge_expedient(&mut merged, from),
+                    Some(_) => {}
+                    None => return ErrorKind::NotFound.into(),
+                }
+            }
+            merged
+        };
+
+        let mut transaction = Transaction::new();
+        transaction.update(into_id, merged);
+        let mut deleted = vec![];
+        for id in from_ids {
+            if *id != into_id && !deleted.contains(id) {
+                transaction.merge(*id, into_id);
+                deleted.push(*id);
+            }
+        }
+        self.commit_accepted(transaction)?;
+        Ok(into_id)
+    }
+
+    /// Expedient the deleted one was merged into, `None` if it was not merged
+    pub fn merged_into(&self, id: Uid) -> Option<Uid> {
+        self.database.read().unwrap().merged_into(id)
+    }
+}
@@ -0,0 +1,138 @@
+pub use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExpedientField {
+    User,
+    Model,
+    LicensePlate,
+    Vin,
+    Description,
+    Orders,
+    Date,
+}
+
+/// Expedient with the changes of both sides, conflicting fields keep the value of `theirs`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ThreeWayMerge {
+    pub expedient: Expedient,
+    /// Fields changed differently on both sides
+    pub conflicts: Vec<ExpedientField>,
+}
+
+/// Takes the field of `ours` if only it changed it from `base`
+fn merge_field<T: PartialEq + Clone>(
+    field: ExpedientField,
+    base: &T,
+    ours: &T,
+    theirs: &mut T,
+    conflicts: &mut Vec<ExpedientField>,
+) {
+    if ours == base || ours == theirs {
+        return;
+    }
+    if theirs == base {
+        *theirs = ours.clone();
+    } else {
+        conflicts.push(field);
+    }
+}
+
+impl Expedient {
+    /// Applies the changes from `base` to `self` on `theirs`, an expedient that also changed from `base`
+    pub fn merge_three_way(&self, base: &Expedient, theirs: &Expedient) -> ThreeWayMerge {
+        let mut expedient = theirs.clone();
+        let mut conflicts = vec![];
+        merge_field(
+            ExpedientField::User,
+            &base.user,
+            &self.user,
+            &mut expedient.user,
+            &mut conflicts,
+        );
+        merge_field(
+            ExpedientField::Model,
+            &base.model,
+            &self.model,
+            &mut expedient.model,
+            &mut conflicts,
+        );
+        merge_field(
+            ExpedientField::LicensePlate,
+            &base.license_plate,
+            &self.license_plate,
+            &mut expedient.license_plate,
+            &mut conflicts,
+        );
+        merge_field(
+            ExpedientField::Vin,
+            &base.vin,
+            &self.vin,
+            &mut expedient.vin,
+            &mut conflicts,
+        );
+        merge_field(
+            ExpedientField::Description,
+            &base.description,
+            &self.description,
+            &mut expedient.description,
+            &mut conflicts,
+        );
+        merge_field(
+            ExpedientField::Orders,
+            &base.orders,
+            &self.orders,
+            &mut expedient.orders,
+            &mut conflicts,
+        );
+        merge_field(
+            ExpedientField::Date,
+            &base.date,
+            &self.date,
+            &mut expedient.date,
+            &mut conflicts,
+        );
+
+        ThreeWayMerge {
+            expedient,
+            conflicts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn merge_non_conflicting_fields() {
+        let base = Expedient {
+            description: "".into(),
+            license_plate: "1234 BCD".into(),
+            model: "Seat Ibiza".into(),
+            orders: vec![],
+            user: "Pedro".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        };
+        let ours = Expedient {
+            user: "Pedro 972 123 456".into(),
+            model: "Seat Leon".into(),
+            ..base.clone()
+        };
+        let theirs = Expedient {
+            description: "Revisar frenos".into(),
+            model: "Seat Ibiza 1.4".into(),
+            ..base.clone()
+        };
+
+        let merge = ours.merge_three_way(&base, &theirs);
+        assert_eq!(vec![ExpedientField::Model], merge.conflicts);
+        assert_eq!("Pedro 972 123 456", merge.expedient.user);
+        assert_eq!("Seat Ibiza 1.4", merge.expedient.model);
+        assert_eq!("Revisar frenos", merge.expedient.description);
+
+        let merge = ours.merge_three_way(&base, &base);
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(ours, merge.expedient);
+    }
+}
@@ -1,5 +1,9 @@
+mod merge;
+mod patch;
 mod similarity;
 use crate::{chunked_database, collections::UtcDate};
+pub use merge::*;
+pub use patch::*;
 use serde::{Deserialize, Serialize};
 pub use similarity::*;
 
@@ -85,6 +89,9 @@ impl chunked_database::Item for Expedient {
     fn date(&self) -> i64 {
         self.newest_date().date_hash()
     }
+    fn part_states(&self) -> Vec<u8> {
+        self.orders.iter().map(|order| order.state as u8).collect()
+    }
 }
 
 #[cfg(test)]
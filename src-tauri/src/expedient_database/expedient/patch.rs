@@ -0,0 +1,116 @@
+pub use super::*;
+
+/// Minimal change of an expedient, sent instead of the whole expedient
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ExpedientPatch {
+    SetUser(String),
+    SetModel(String),
+    SetLicensePlate(String),
+    SetVin(String),
+    SetDescription(String),
+    SetDate(UtcDate),
+    InsertOrder { index: usize, order: Order },
+    RemoveOrder(usize),
+    UpdateOrder { index: usize, order: Order },
+}
+
+impl Expedient {
+    /// Applies the patch, returns `false` if the index of the order is out of range
+    pub fn apply_patch(&mut self, patch: ExpedientPatch) -> bool {
+        match patch {
+            ExpedientPatch::SetUser(value) => self.user = value,
+            ExpedientPatch::SetModel(value) => self.model = value,
+            ExpedientPatch::SetLicensePlate(value) => self.license_plate = value,
+            ExpedientPatch::SetVin(value) => self.vin = value,
+            ExpedientPatch::SetDescription(value) => self.description = value,
+            ExpedientPatch::SetDate(date) => self.date = date,
+            ExpedientPatch::InsertOrder { index, order } => {
+                if index > self.orders.len() {
+                    return false;
+                }
+                self.orders.insert(index, order);
+            }
+            ExpedientPatch::RemoveOrder(index) => {
+                if index >= self.orders.len() {
+                    return false;
+                }
+                self.orders.remove(index);
+            }
+            ExpedientPatch::UpdateOrder { index, order } => match self.orders.get_mut(index) {
+                Some(stored) => *stored = order,
+                None => return false,
+            },
+        }
+        true
+    }
+
+    /// Applies all the patches, or none of them if one fails
+    pub fn apply_patches(&mut self, patches: Vec<ExpedientPatch>) -> bool {
+        let mut patched = self.clone();
+        if !patches.into_iter().all(|patch| patched.apply_patch(patch)) {
+            return false;
+        }
+        *self = patched;
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn apply_all_patches_or_none() {
+        let order = |title: &str| Order {
+            date: UtcDate::ymdh(2022, 3, 1, 9),
+            title: title.into(),
+            description: "".into(),
+            state: OrderState::Todo,
+        };
+        let original = Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "Seat Ibiza".into(),
+            orders: vec![order("Frenos")],
+            user: "Pedro".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        };
+
+        let mut expedient = original.clone();
+        assert!(expedient.apply_patches(vec![
+            ExpedientPatch::SetModel("Seat Leon".into()),
+            ExpedientPatch::InsertOrder {
+                index: 0,
+                order: order("Aceite"),
+            },
+            ExpedientPatch::UpdateOrder {
+                index: 1,
+                order: order("Frenos y pastillas"),
+            },
+        ]));
+        assert_eq!("Seat Leon", expedient.model);
+        assert_eq!(
+            vec![order("Aceite"), order("Frenos y pastillas")],
+            expedient.orders
+        );
+
+        // A failed patch doesn't apply the previous ones
+        let patched = expedient.clone();
+        assert!(!expedient.apply_patches(vec![
+            ExpedientPatch::SetUser("Eduardo".into()),
+            ExpedientPatch::RemoveOrder(2),
+        ]));
+        assert_eq!(patched, expedient);
+
+        assert!(expedient.apply_patches(vec![
+            ExpedientPatch::RemoveOrder(0),
+            ExpedientPatch::SetModel("Seat Ibiza".into()),
+            ExpedientPatch::UpdateOrder {
+                index: 0,
+                order: order("Frenos"),
+            },
+        ]));
+        assert_eq!(original, expedient);
+    }
+}
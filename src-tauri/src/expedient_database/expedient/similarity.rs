@@ -1,5 +1,6 @@
 pub use super::*;
-use crate::mean::*;
+use crate::{license_plate, mean::*, vin};
+use serde::{Deserialize, Serialize};
 
 pub trait Similarity {
     fn similarity(&self, other: &Self) -> f32;
@@ -53,19 +54,183 @@ impl Similarity for Order {
     }
 }
 
-impl Similarity for Expedient {
-    fn similarity(&self, other: &Self) -> f32 {
+/// Weight of each field on the similarity of two expedients
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SimilarityWeights {
+    pub user: f32,
+    pub orders: f32,
+    pub description: f32,
+    pub model: f32,
+    pub license_plate: f32,
+    pub vin: f32,
+}
+
+impl Default for SimilarityWeights {
+    fn default() -> Self {
+        Self {
+            user: 2.,
+            orders: 1.,
+            description: 1.,
+            model: 1.,
+            license_plate: 10.,
+            vin: 10.,
+        }
+    }
+}
+
+/// Higher weights could overflow the similarity scores
+pub const MAX_SIMILARITY_WEIGHT: f32 = 1000.;
+
+impl SimilarityWeights {
+    /// All the weights are between 0 and `MAX_SIMILARITY_WEIGHT`, and at least one is positive
+    pub fn is_valid(&self) -> bool {
+        let weights = [
+            self.user,
+            self.orders,
+            self.description,
+            self.model,
+            self.license_plate,
+            self.vin,
+        ];
+        weights
+            .iter()
+            .all(|weight| (0. ..=MAX_SIMILARITY_WEIGHT).contains(weight))
+            && weights.iter().sum::<f32>() > 0.
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SimilarityField {
+    User,
+    Orders,
+    Description,
+    Model,
+    LicensePlate,
+    Vin,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldSimilarity {
+    pub field: SimilarityField,
+    pub similarity: f32,
+    pub weight: f32,
+}
+
+/// Similarity of two expedients and how much each field contributes to it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimilarityBreakdown {
+    pub similarity: f32,
+    /// Fields empty on both expedients are not compared
+    pub fields: Vec<FieldSimilarity>,
+}
+
+impl Expedient {
+    pub fn weighted_similarity(&self, other: &Self, weights: &SimilarityWeights) -> f32 {
+        self.field_similarities(other, weights)
+            .into_iter()
+            .filter_map(|(_, similarity)| similarity)
+            .weighted_mean()
+    }
+
+    pub fn similarity_breakdown(
+        &self,
+        other: &Self,
+        weights: &SimilarityWeights,
+    ) -> SimilarityBreakdown {
+        let fields = self.field_similarities(other, weights);
+        SimilarityBreakdown {
+            similarity: fields
+                .iter()
+                .filter_map(|(_, similarity)| *similarity)
+                .weighted_mean(),
+            fields: fields
+                .into_iter()
+                .filter_map(|(field, similarity)| {
+                    let (similarity, weight) = similarity?;
+                    Some(FieldSimilarity {
+                        field,
+                        similarity,
+                        weight,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    fn field_similarities(
+        &self,
+        other: &Self,
+        weights: &SimilarityWeights,
+    ) -> [(SimilarityField, Option<(f32, f32)>); 6] {
         [
-            match_if_exist(&self.user, &other.user, 2.),
-            mean_match_if_exist(&self.orders, &other.orders, 1.),
-            match_if_exist(&self.description, &other.description, 1.),
-            match_if_exist(&self.model, &other.model, 1.),
-            match_if_exist(&self.license_plate, &other.license_plate, 10.),
-            match_if_exist(&self.vin, &other.vin, 10.),
+            (
+                SimilarityField::User,
+                match_if_exist(&self.user, &other.user, weights.user),
+            ),
+            (
+                SimilarityField::Orders,
+                mean_match_if_exist(&self.orders, &other.orders, weights.orders),
+            ),
+            (
+                SimilarityField::Description,
+                match_if_exist(&self.description, &other.description, weights.description),
+            ),
+            (
+                SimilarityField::Model,
+                match_if_exist(&self.model, &other.model, weights.model),
+            ),
+            (
+                SimilarityField::LicensePlate,
+                match_if_exist(
+                    &self.license_plate,
+                    &other.license_plate,
+                    weights.license_plate,
+                ),
+            ),
+            (
+                SimilarityField::Vin,
+                match_if_exist(&self.vin, &other.vin, weights.vin),
+            ),
         ]
-        .into_iter()
-        .flatten()
-        .weighted_mean()
+    }
+}
+
+impl Similarity for Expedient {
+    fn similarity(&self, other: &Self) -> f32 {
+        self.weighted_similarity(other, &Default::default())
+    }
+}
+
+impl Expedient {
+    /// Expedient with only the fields that are filled on `filled` and without orders,
+    /// so the similarity is not lowered by the fields missing on `filled`.
+    /// License plate and VIN are compared without separators.
+    pub fn masked_like(&self, filled: &Self) -> Self {
+        let field = |value: &String, filled: &String| {
+            if filled.trim().is_empty() {
+                String::new()
+            } else {
+                value.clone()
+            }
+        };
+        Self {
+            user: field(&self.user, &filled.user),
+            model: field(&self.model, &filled.model),
+            license_plate: license_plate::normalize(&field(
+                &self.license_plate,
+                &filled.license_plate,
+            )),
+            vin: vin::normalize(&field(&self.vin, &filled.vin)),
+            description: field(&self.description, &filled.description),
+            orders: vec![],
+            date: self.date,
+        }
+    }
+
+    /// Similarity of the fields filled on both expedients
+    pub fn similarity_of_filled(&self, other: &Self, weights: &SimilarityWeights) -> f32 {
+        self.masked_like(other)
+            .weighted_similarity(&other.masked_like(self), weights)
     }
 }
 
@@ -315,4 +480,43 @@ mod test {
             })
         );
     }
+
+    #[test]
+    fn weighted_expedient_breakdown() {
+        let expedient = |user: &str, vin: &str| Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "".into(),
+            orders: vec![],
+            user: user.into(),
+            vin: vin.into(),
+            date: UtcDate::ymdh(2010, 1, 3, 23),
+        };
+        let a = expedient("Pepa 923149288", "2HGES16503H591599");
+        let b = expedient("Pepa Rodrigo", "2HGES16503H591599");
+
+        let breakdown = a.similarity_breakdown(&b, &Default::default());
+        assert_eq!(a.similarity(&b), breakdown.similarity);
+        assert_eq!(
+            vec![
+                FieldSimilarity {
+                    field: SimilarityField::User,
+                    similarity: 0.5,
+                    weight: 2.,
+                },
+                FieldSimilarity {
+                    field: SimilarityField::Vin,
+                    similarity: 1.,
+                    weight: 10.,
+                },
+            ],
+            breakdown.fields
+        );
+
+        let weights = SimilarityWeights {
+            user: 10.,
+            ..Default::default()
+        };
+        assert_eq!(0.75, a.weighted_similarity(&b, &weights));
+    }
 }
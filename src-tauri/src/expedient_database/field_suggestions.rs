@@ -0,0 +1,125 @@
+use super::*;
+use crate::{license_plate, vin};
+use std::collections::HashMap;
+
+/// Value for an empty field of an expedient, taken from other expedients of the same vehicle or customer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldSuggestion {
+    pub field: SuggestionField,
+    pub value: String,
+    /// Mean similarity of the expedients that have this value, counting as 0 the ones with a different value
+    pub confidence: f32,
+}
+
+/// Below this similarity two users are not considered the same customer
+const MIN_CUSTOMER_SIMILARITY: f32 = 0.75;
+
+fn has_phone(user: &str) -> bool {
+    user.chars().filter(|c| c.is_ascii_digit()).count() >= 6
+}
+
+#[derive(Default)]
+struct FieldVotes<'b> {
+    /// Normalized value -> (first seen value, sum of similarities)
+    values: HashMap<String, (&'b str, f32)>,
+    voters: usize,
+}
+
+impl<'b> FieldVotes<'b> {
+    fn vote(&mut self, value: &'b str, similarity: f32) {
+        let value = value.trim();
+        if value.is_empty() {
+            return;
+        }
+        let key = value
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        self.values.entry(key).or_insert((value, 0.)).1 += similarity;
+        self.voters += 1;
+    }
+
+    fn suggestions(self, field: SuggestionField) -> impl Iterator<Item = FieldSuggestion> + 'b {
+        let voters = self.voters as f32;
+        self.values
+            .into_values()
+            .map(move |(value, similarity)| FieldSuggestion {
+                field,
+                value: value.into(),
+                confidence: similarity / voters,
+            })
+    }
+}
+
+impl<'a> ExpedientDatabase<'a> {
+    /// Suggests values for the empty fields of `expedient`: the ones of the expedients
+    /// with the same VIN or license plate, and the phone of the same customer.
+    pub fn suggest_fields(&self, expedient: &Expedient) -> Vec<FieldSuggestion> {
+        let weights = self.similarity_weights();
+        let database = self.database.read().unwrap();
+
+        let vin = vin::normalize(&expedient.vin);
+        let license_plate = license_plate::normalize(&expedient.license_plate);
+        let filled = expedient.masked_like(expedient);
+        let suggest_user = expedient.user.trim().is_empty() || !has_phone(&expedient.user);
+
+        let mut users = FieldVotes::default();
+        let mut models = FieldVotes::default();
+        let mut license_plates = FieldVotes::default();
+        let mut vins = FieldVotes::default();
+
+        for (_, other) in database.iter_all() {
+            let same_vehicle = (!vin.is_empty() && vin::normalize(&other.vin) == vin)
+                || (!license_plate.is_empty()
+                    && license_plate::normalize(&other.license_plate) == license_plate);
+            let same_customer = suggest_user
+                && !expedient.user.trim().is_empty()
+                && has_phone(&other.user)
+                && expedient.user.similarity(&other.user) >= MIN_CUSTOMER_SIMILARITY;
+
+            if !same_vehicle && !same_customer {
+                continue;
+            }
+            let similarity = other
+                .masked_like(expedient)
+                .weighted_similarity(&filled, &weights);
+
+            if suggest_user {
+                users.vote(&other.user, similarity);
+            }
+            if same_vehicle {
+                models.vote(&other.model, similarity);
+                license_plates.vote(&other.license_plate, similarity);
+                vins.vote(&other.vin, similarity);
+            }
+        }
+
+        let mut suggestions: Vec<_> = [
+            (SuggestionField::User, users, suggest_user),
+            (
+                SuggestionField::Model,
+                models,
+                expedient.model.trim().is_empty(),
+            ),
+            (
+                SuggestionField::LicensePlate,
+                license_plates,
+                license_plate.is_empty(),
+            ),
+            (SuggestionField::Vin, vins, vin.is_empty()),
+        ]
+        .into_iter()
+        .filter(|(_, _, is_empty)| *is_empty)
+        .flat_map(|(field, votes, _)| votes.suggestions(field))
+        .collect();
+
+        suggestions.sort_by(|a, b| {
+            (a.field as u8)
+                .cmp(&(b.field as u8))
+                .then(b.confidence.total_cmp(&a.confidence))
+                .then(a.value.cmp(&b.value))
+        });
+        suggestions
+    }
+}
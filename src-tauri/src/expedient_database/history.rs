@@ -0,0 +1,156 @@
+use super::*;
+use std::collections::VecDeque;
+
+/// Above this amount of entries the oldest ones are forgotten
+const MAX_HISTORY_LEN: usize = 100;
+
+/// Expedient before and after a change, `None` when it doesn't exist
+#[derive(Debug, Clone)]
+pub(super) struct Mutation {
+    pub id: Uid,
+    pub before: Option<Expedient>,
+    pub after: Option<Expedient>,
+    /// Expedient the deleted one was merged into
+    pub merged_into: Option<Uid>,
+    /// Expedients linked to this one by the mutation
+    pub links: Vec<Uid>,
+}
+
+impl Mutation {
+    pub fn new(id: Uid, before: Option<Expedient>, after: Option<Expedient>) -> Self {
+        Self {
+            id,
+            before,
+            after,
+            merged_into: None,
+            links: vec![],
+        }
+    }
+
+    fn replace_id(&mut self, old_id: Uid, new_id: Uid) {
+        if self.id == old_id {
+            self.id = new_id;
+        }
+        if self.merged_into == Some(old_id) {
+            self.merged_into = Some(new_id);
+        }
+        for linked_id in self.links.iter_mut() {
+            if *linked_id == old_id {
+                *linked_id = new_id;
+            }
+        }
+    }
+}
+
+/// Mutations done by the database, each entry is undone at once
+#[derive(Debug, Default)]
+pub(super) struct History {
+    undo: VecDeque<Vec<Mutation>>,
+    redo: Vec<Vec<Mutation>>,
+}
+
+impl History {
+    pub fn record(&mut self, mutations: Vec<Mutation>) {
+        if mutations.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push_back(mutations);
+        if self.undo.len() > MAX_HISTORY_LEN {
+            self.undo.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Deleted expedients are created again with a new `Uid`
+    fn replace_id(&mut self, old_id: Uid, new_id: Uid) {
+        for mutation in self.undo.iter_mut().chain(self.redo.iter_mut()).flatten() {
+            mutation.replace_id(old_id, new_id);
+        }
+    }
+}
+
+impl<'a> ExpedientDatabase<'a> {
+    /// Writes the expedients as they were before or after the mutations,
+    /// and returns the ones that exist after it
+    fn write_images(&mut self, mutations: &mut [Mutation], before: bool) -> Vec<Uid> {
+        let order: Vec<_> = if before {
+            (0..mutations.len()).rev().collect()
+        } else {
+            (0..mutations.len()).collect()
+        };
+
+        self.interrupt_dispatch();
+        let mut changes = ChangeSet::default();
+        {
+            let mut database = self.database.write().unwrap();
+            for index in order {
+                let id = mutations[index].id;
+                let image = if before {
+                    mutations[index].before.clone()
+                } else {
+                    mutations[index].after.clone()
+                };
+                if before {
+                    database.forget_merge(id);
+                }
+                match image {
+                    None => {
+                        database.delete(id);
+                        if let Some(into) = mutations[index].merged_into {
+                            database.record_merge(id, into);
+                        }
+                        changes.deleted.push(id);
+                    }
+                    Some(expedient) if database.read(id).is_some() => {
+                        database.update(id, expedient);
+                        changes.updated.push(id);
+                    }
+                    Some(expedient) => {
+                        let new_id = database.push(expedient);
+                        changes.created.push(new_id);
+                        for mutation in mutations.iter_mut() {
+                            mutation.replace_id(id, new_id);
+                        }
+                        self.history.replace_id(id, new_id);
+                    }
+                }
+            }
+            // Once all the expedients exist with their current ids
+            for mutation in mutations.iter() {
+                for linked_id in &mutation.links {
+                    if before {
+                        database.unlink(mutation.id, *linked_id);
+                    } else {
+                        database.link(mutation.id, *linked_id);
+                    }
+                }
+            }
+        }
+        let changed = changes.changed();
+        self.dispatch_change(changes);
+        changed
+    }
+
+    /// Reverts the last mutation, returns the expedients that exist after it
+    /// or `None` if there is nothing to undo
+    pub fn undo(&mut self) -> Option<Vec<Uid>> {
+        let mut mutations = self.history.undo.pop_back()?;
+        let changed = self.write_images(&mut mutations, true);
+        self.history.redo.push(mutations);
+        Some(changed)
+    }
+
+    /// Does again the last undone mutation, returns the expedients that exist after it
+    /// or `None` if there is nothing to redo
+    pub fn redo(&mut self) -> Option<Vec<Uid>> {
+        let mut mutations = self.history.redo.pop()?;
+        let changed = self.write_images(&mut mutations, false);
+        self.history.undo.push_back(mutations);
+        Some(changed)
+    }
+}
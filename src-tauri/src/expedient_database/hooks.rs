@@ -1,17 +1,50 @@
 use super::filter::Filter;
 use super::*;
-use crate::{chunked_database::*, observable::*};
+use crate::{chunked_database::*, database::Database, license_plate, observable::*, vin};
 use std::{
+    cmp::Ordering,
     collections::HashMap,
+    ops::Range,
     sync::{Arc, Mutex, RwLock},
+    time::Duration,
 };
 
-#[derive(Default)]
 pub struct HookPool<'a> {
     observable: Observable<HookContext<'a>>,
-    list_observable: AsyncObservable<'a, ListExpedientsHookContext<'a>>,
-    list_orders_observable: AsyncObservable<'a, ListOrdersHookContext<'a>>,
-    list_filter: AsyncObservable<'a, ListFilterHookContext<'a>>,
+    saved_searches: Observable<SavedSearchesHookContext>,
+    list_observable: AsyncObservable<ListExpedientsHookContext>,
+    list_orders_observable: AsyncObservable<ListOrdersHookContext>,
+    suggestions: AsyncObservable<SuggestionsHookContext>,
+    smart_search: AsyncObservable<SmartSearchHookContext>,
+    duplicates: AsyncObservable<DuplicatesHookContext>,
+    /// List hooks wait this time before recomputing,
+    /// so all the changes dispatched meanwhile are computed only once.
+    dispatch_window: Duration,
+    workers: Arc<WorkerPool>,
+}
+
+const DEFAULT_DISPATCH_WINDOW: Duration = Duration::from_millis(100);
+
+impl<'a> HookPool<'a> {
+    fn new(workers: Arc<WorkerPool>, dispatch_window: Duration) -> Self {
+        Self {
+            observable: Default::default(),
+            saved_searches: Default::default(),
+            list_observable: AsyncObservable::new(workers.clone()),
+            list_orders_observable: AsyncObservable::new(workers.clone()),
+            suggestions: AsyncObservable::new(workers.clone()),
+            smart_search: AsyncObservable::new(workers.clone()),
+            duplicates: AsyncObservable::new(workers.clone()),
+            dispatch_window,
+            workers,
+        }
+    }
+}
+
+impl<'a> Default for HookPool<'a> {
+    fn default() -> Self {
+        Self::new(Default::default(), DEFAULT_DISPATCH_WINDOW)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -19,7 +52,10 @@ pub enum HookId {
     Expedient(Id),
     ListExpedients(Id),
     ListExpedientOrders(Id),
-    ListFilter(Id),
+    Suggestions(Id),
+    SavedSearches(Id),
+    SmartSearch(Id),
+    Duplicates(Id),
 }
 
 // Expedient Hook
@@ -28,33 +64,63 @@ pub enum HookId {
 struct HookContext<'a> {
     pub database: Arc<RwLock<ChunkedDatabase<Expedient>>>,
     pub expedient_id: Uid,
-    pub callback: Arc<Mutex<Box<dyn for<'r> FnMut(Option<&'r Expedient>) + Send + Sync + 'a>>>,
+    pub callback:
+        Arc<Mutex<Box<dyn for<'r> FnMut(Option<(&'r Expedient, u32)>) + Send + Sync + 'a>>>,
+}
+
+// Saved Searches Hook
+
+struct SavedSearchesHookContext {
+    pub saved_searches: Arc<RwLock<Database<SavedSearches>>>,
+    pub callback: Arc<Mutex<Box<dyn for<'r> FnMut(&Vec<&'r str>) + Send + Sync>>>,
 }
 
 // List of Expedients Hook
 
 #[derive(Clone)]
-struct ListExpedientsHookContext<'a> {
+struct ListExpedientsHookContext {
     pub database: Arc<RwLock<ChunkedDatabase<Expedient>>>,
-    pub callback:
-        Arc<Mutex<Box<dyn for<'r> FnMut(&Vec<(Uid, &'r Expedient, f32)>) + Send + Sync + 'a>>>,
+    pub callback: Arc<Mutex<Box<dyn for<'r> FnMut(&ListExpedientsPage<'r>) + Send + Sync>>>,
     pub options: ListExpedientsHookOptions,
+    pub similarity_weights: Arc<RwLock<Database<SimilarityWeights>>>,
+    pub changes: Arc<Mutex<ChangeSet>>,
+    /// Result of the last completed scan sorted by similarity
+    pub sorted_expedients: Arc<Mutex<Option<Vec<(Uid, f32)>>>>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ListExpedientsHookOptions {
     pub filter: Expedient,
     pub max_list_len: usize,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListExpedientsPage<'a> {
+    pub expedients: Vec<(Uid, &'a Expedient, f32)>,
+    pub total_len: usize,
 }
 
 // List of Expedient Orders Hook
 
 #[derive(Clone)]
-struct ListOrdersHookContext<'a> {
+struct ListOrdersHookContext {
     pub database: Arc<RwLock<ChunkedDatabase<Expedient>>>,
-    pub callback:
-        Arc<Mutex<Box<dyn for<'r> FnMut(&Vec<(Uid, usize, &'r Expedient)>) + Send + Sync + 'a>>>,
-    pub options: ListOrdersHookOptions,
+    pub callback: Arc<Mutex<Box<dyn for<'r> FnMut(&ListOrdersPage<'r>) + Send + Sync>>>,
+    pub options: Arc<RwLock<ListOrdersHookOptions>>,
+    /// Name of the saved search the options are read from
+    pub saved_search: Option<String>,
+    pub cursor: Arc<Mutex<Option<ListOrdersCursor>>>,
+    pub changes: Arc<Mutex<ChangeSet>>,
+    /// Result of the last completed scan, used to serve other pages without filtering again
+    pub sorted_orders: Arc<Mutex<Option<SortedOrders>>>,
+}
+
+/// Orders of a completed scan and their facets, counted once for all the pages
+struct SortedOrders {
+    keys: Vec<ListOrdersCursor>,
+    facets: Option<ListOrdersFacets>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -73,6 +139,12 @@ impl ListOrdersHookFilter {
     }
 }
 
+/// Above this amount of changes, it's faster to scan again all the database
+const MAX_INCREMENTAL_CHANGES: usize = 64;
+
+/// Below this amount of expedients per partition, a thread costs more than it saves
+const MIN_PARTITION_LEN: usize = 512;
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ListOrdersHookOptions {
     pub filter: Option<ListOrdersHookFilter>,
@@ -84,19 +156,419 @@ pub struct ListOrdersHookOptions {
     pub show_awaiting: bool,
     pub show_instore: bool,
     pub show_done: bool,
+    /// Only orders after the cursor (in the `sort_by` order) are listed
+    #[serde(default)]
+    pub cursor: Option<ListOrdersCursor>,
+    /// Orders to skip after the cursor
+    #[serde(default)]
+    pub offset: usize,
+    /// Orders with the same value are listed together, then sorted by `sort_by`
+    #[serde(default)]
+    pub group_by: Option<ListOrdersSortKey>,
+    /// Send with every page the `ListOrdersFacets` of the whole list
+    #[serde(default)]
+    pub facets: bool,
+    /// Orders with a date inside the range, `from_date` still limits the newest ones
+    #[serde(default)]
+    pub order_date: DateRange,
+    #[serde(default)]
+    pub expedient_date: DateRange,
+    /// Last time the expedient was created or modified
+    #[serde(default)]
+    pub modified_date: DateRange,
+}
+
+impl ListOrdersHookOptions {
+    /// Lowercases the filter and sorts first by the `group_by` key
+    fn prepare(&mut self) {
+        if let Some(ref mut filter) = self.filter {
+            filter.to_lowercase();
+        }
+        if let Some(group_by) = self.group_by {
+            let mut keys = vec![group_by];
+            keys.extend_from_slice(self.sort_by.keys());
+            self.sort_by = ListOrdersHookOptionsSortBy::Keys(keys);
+        }
+    }
+
+    /// When filtering by popularity, a change on one expedient
+    /// can add or remove orders of other expedients.
+    fn depends_on_all_expedients(&self) -> bool {
+        self.filter
+            .as_ref()
+            .map_or(false, |filter| filter.popularity != 0)
+    }
+
+    /// Ranges relative to the current date move, so orders not changed can enter or leave them
+    fn has_relative_dates(&self) -> bool {
+        self.order_date.is_relative()
+            || self.expedient_date.is_relative()
+            || self.modified_date.is_relative()
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum ListOrdersHookOptionsSortBy {
     Oldest,
     Newest,
+    /// Sorts by the first key, orders with the same value by the next key, and so on
+    Keys(Vec<ListOrdersSortKey>),
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct ListOrdersSortKey {
+    pub field: ListOrdersSortField,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub enum ListOrdersSortField {
+    /// Urgent first, done last
+    State,
+    Customer,
+    Model,
+    LicensePlate,
+    Date,
+    /// Time since the state of the order last changed, or since its date if it never changed
+    TimeInState,
+    /// License plate, or VIN if the vehicle has no plate
+    Vehicle,
+    Day,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ListOrdersSortValue {
+    Number(i64),
+    Text(String),
+}
+
+const SORT_BY_OLDEST: [ListOrdersSortKey; 1] = [ListOrdersSortKey {
+    field: ListOrdersSortField::Date,
+    descending: false,
+}];
+const SORT_BY_NEWEST: [ListOrdersSortKey; 1] = [ListOrdersSortKey {
+    field: ListOrdersSortField::Date,
+    descending: true,
+}];
+
+impl ListOrdersHookOptionsSortBy {
+    fn keys(&self) -> &[ListOrdersSortKey] {
+        match self {
+            ListOrdersHookOptionsSortBy::Oldest => &SORT_BY_OLDEST,
+            ListOrdersHookOptionsSortBy::Newest => &SORT_BY_NEWEST,
+            ListOrdersHookOptionsSortBy::Keys(keys) => keys,
+        }
+    }
+}
+
+impl ListOrdersSortField {
+    /// `state_changed` is the date the order got its current state
+    fn value(
+        self,
+        expedient: &Expedient,
+        order: &Order,
+        state_changed: UtcDate,
+    ) -> ListOrdersSortValue {
+        let text = |text: &str| {
+            ListOrdersSortValue::Text(
+                text.split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .to_lowercase(),
+            )
+        };
+        match self {
+            ListOrdersSortField::State => ListOrdersSortValue::Number(match order.state {
+                OrderState::Urgent => 0,
+                OrderState::Todo => 1,
+                OrderState::Awaiting => 2,
+                OrderState::InStore => 3,
+                OrderState::Done => 4,
+            }),
+            ListOrdersSortField::Customer => text(&expedient.user),
+            ListOrdersSortField::Model => text(&expedient.model),
+            ListOrdersSortField::LicensePlate => {
+                ListOrdersSortValue::Text(license_plate::normalize(&expedient.license_plate))
+            }
+            ListOrdersSortField::Date => ListOrdersSortValue::Number(order.date.date_hash()),
+            ListOrdersSortField::TimeInState => {
+                ListOrdersSortValue::Number(-state_changed.date_hash())
+            }
+            ListOrdersSortField::Vehicle => {
+                ListOrdersSortValue::Text(license_plate::normalize(Self::vehicle(expedient)))
+            }
+            ListOrdersSortField::Day => ListOrdersSortValue::Number(order.date.day_hash()),
+        }
+    }
+
+    fn header(
+        self,
+        expedient: &Expedient,
+        order: &Order,
+        value: &ListOrdersSortValue,
+    ) -> ListOrdersGroupHeader {
+        match self {
+            ListOrdersSortField::State => ListOrdersGroupHeader::State(order.state),
+            ListOrdersSortField::Customer => {
+                ListOrdersGroupHeader::Text(expedient.user.trim().into())
+            }
+            ListOrdersSortField::Model => {
+                ListOrdersGroupHeader::Text(expedient.model.trim().into())
+            }
+            ListOrdersSortField::LicensePlate => {
+                ListOrdersGroupHeader::Text(expedient.license_plate.trim().into())
+            }
+            ListOrdersSortField::Vehicle => {
+                ListOrdersGroupHeader::Text(Self::vehicle(expedient).trim().into())
+            }
+            ListOrdersSortField::Date => ListOrdersGroupHeader::Date(order.date),
+            ListOrdersSortField::TimeInState => match value {
+                ListOrdersSortValue::Number(hash) => {
+                    ListOrdersGroupHeader::Date(UtcDate::from_hash(-hash))
+                }
+                ListOrdersSortValue::Text(_) => ListOrdersGroupHeader::Date(order.date),
+            },
+            ListOrdersSortField::Day => ListOrdersGroupHeader::Date(order.date.day()),
+        }
+    }
+
+    fn vehicle(expedient: &Expedient) -> &str {
+        if expedient.license_plate.trim().is_empty() {
+            &expedient.vin
+        } else {
+            &expedient.license_plate
+        }
+    }
+}
+
+/// Identifies an order on a sorted list.
+/// Orders are sorted by the values of the sort keys, and orders with the same values
+/// by `Uid` and index, so a cursor stays valid when other orders are inserted or deleted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListOrdersCursor {
+    pub sort_values: Vec<ListOrdersSortValue>,
+    pub expedient_id: Uid,
+    pub order_index: usize,
+}
+
+impl ListOrdersCursor {
+    fn new(
+        sort_by: &ListOrdersHookOptionsSortBy,
+        expedient_id: Uid,
+        order_index: usize,
+        expedient: &Expedient,
+        database: &ChunkedDatabase<Expedient>,
+    ) -> Self {
+        let order = &expedient.orders[order_index];
+        let state_changed = database
+            .state_changed(expedient_id, order_index)
+            .unwrap_or(order.date);
+        Self {
+            sort_values: sort_by
+                .keys()
+                .iter()
+                .map(|key| key.field.value(expedient, order, state_changed))
+                .collect(),
+            expedient_id,
+            order_index,
+        }
+    }
+
+    fn cmp_sorted_by(&self, other: &Self, sort_by: &ListOrdersHookOptionsSortBy) -> Ordering {
+        sort_by
+            .keys()
+            .iter()
+            .zip(self.sort_values.iter().zip(other.sort_values.iter()))
+            .map(|(key, (a, b))| if key.descending { b.cmp(a) } else { a.cmp(b) })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| {
+                (self.expedient_id, self.order_index).cmp(&(other.expedient_id, other.order_index))
+            })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListOrdersPage<'a> {
+    pub orders: Vec<(Uid, usize, &'a Expedient)>,
+    /// Groups of the orders of the page, empty if the list is not grouped
+    pub groups: Vec<ListOrdersGroup>,
+    /// Length of the list without paging
+    pub total_len: usize,
+    /// Cursor to request the next page, `None` if this is the last one
+    pub next_cursor: Option<ListOrdersCursor>,
+    pub facets: Option<ListOrdersFacets>,
 }
 
+/// Amount of orders of the list with each value
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ListOrdersFacets {
+    pub states: Vec<(OrderState, usize)>,
+    /// Sorted by count, models that only differ in case or spaces are counted together
+    pub models: Vec<(String, usize)>,
+    /// Sorted by count, users that only differ in case or spaces are counted together
+    pub users: Vec<(String, usize)>,
+    /// First day of the month, sorted by date
+    pub months: Vec<(UtcDate, usize)>,
+}
+
+impl ListOrdersFacets {
+    fn count<'b>(orders: impl Iterator<Item = (&'b Expedient, &'b Order)>) -> Self {
+        let mut states = [
+            OrderState::Urgent,
+            OrderState::Todo,
+            OrderState::Awaiting,
+            OrderState::InStore,
+            OrderState::Done,
+        ]
+        .map(|state| (state, 0));
+        let mut models = HashMap::<String, (&str, usize)>::new();
+        let mut users = HashMap::<String, (&str, usize)>::new();
+        let mut months = HashMap::<i64, usize>::new();
+
+        for (expedient, order) in orders {
+            if let Some((_, count)) = states.iter_mut().find(|(state, _)| *state == order.state) {
+                *count += 1;
+            }
+            Self::count_text(&mut models, &expedient.model);
+            Self::count_text(&mut users, &expedient.user);
+            *months.entry(order.date.month().date_hash()).or_default() += 1;
+        }
+
+        let mut months: Vec<_> = months
+            .into_iter()
+            .map(|(month, count)| (UtcDate::from_hash(month), count))
+            .collect();
+        months.sort_unstable_by_key(|(month, _)| month.date_hash());
+
+        Self {
+            states: states.to_vec(),
+            models: Self::sorted_by_count(models),
+            users: Self::sorted_by_count(users),
+            months,
+        }
+    }
+
+    fn count_text<'b>(counts: &mut HashMap<String, (&'b str, usize)>, text: &'b str) {
+        let key = text
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        if !key.is_empty() {
+            counts.entry(key).or_insert((text.trim(), 0)).1 += 1;
+        }
+    }
+
+    fn sorted_by_count(counts: HashMap<String, (&str, usize)>) -> Vec<(String, usize)> {
+        let mut list: Vec<_> = counts
+            .into_values()
+            .map(|(text, count)| (text.to_string(), count))
+            .collect();
+        list.sort_unstable_by(|(text_a, a), (text_b, b)| b.cmp(a).then(text_a.cmp(text_b)));
+        list
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ListOrdersGroupHeader {
+    Text(String),
+    State(OrderState),
+    Date(UtcDate),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ListOrdersGroup {
+    pub header: ListOrdersGroupHeader,
+    /// Orders of the group in this page, they follow the ones of the previous group
+    pub page_len: usize,
+    /// Orders of the group in the whole list
+    pub total_len: usize,
+}
+
+// Suggestions Hook
+
 #[derive(Clone)]
-struct ListFilterHookContext<'a> {
+struct SuggestionsHookContext {
     pub database: Arc<RwLock<ChunkedDatabase<Expedient>>>,
-    pub callback: Arc<Mutex<Box<dyn for<'r> FnMut(&Vec<&str>) + Send + Sync + 'a>>>,
+    pub callback: Arc<Mutex<Box<dyn for<'r> FnMut(&Vec<&str>) + Send + Sync>>>,
+    pub options: SuggestionsHookOptions,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SuggestionsHookOptions {
+    pub field: SuggestionField,
     pub filter: String,
+    #[serde(default = "SuggestionsHookOptions::default_max_list_len")]
+    pub max_list_len: usize,
+}
+
+impl SuggestionsHookOptions {
+    fn default_max_list_len() -> usize {
+        5
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SuggestionField {
+    User,
+    Model,
+    LicensePlate,
+    Vin,
+    OrderTitle,
+    /// Each line of the order descriptions, as the parts or works done are written one per line
+    OrderDescription,
+}
+
+impl SuggestionField {
+    /// Values of the field on an expedient, with the date they were used
+    fn values<'b>(
+        self,
+        expedient: &'b Expedient,
+    ) -> Box<dyn Iterator<Item = (&'b str, UtcDate)> + 'b> {
+        let expedient_value = |value: &'b String| {
+            Box::new(std::iter::once((value.as_str(), expedient.newest_date())))
+        };
+        match self {
+            SuggestionField::User => expedient_value(&expedient.user),
+            SuggestionField::Model => expedient_value(&expedient.model),
+            SuggestionField::LicensePlate => expedient_value(&expedient.license_plate),
+            SuggestionField::Vin => expedient_value(&expedient.vin),
+            SuggestionField::OrderTitle => Box::new(
+                expedient
+                    .orders
+                    .iter()
+                    .map(|order| (order.title.as_str(), order.date)),
+            ),
+            SuggestionField::OrderDescription => {
+                Box::new(expedient.orders.iter().flat_map(|order| {
+                    order
+                        .description
+                        .lines()
+                        .map(move |line| (line, order.date))
+                }))
+            }
+        }
+    }
+}
+
+// Smart Search Hook
+
+#[derive(Clone)]
+struct SmartSearchHookContext {
+    pub database: Arc<RwLock<ChunkedDatabase<Expedient>>>,
+    pub callback: Arc<Mutex<Box<dyn for<'r> FnMut(&SmartSearchPage<'r>) + Send + Sync>>>,
+    pub query: String,
+    pub max_list_len: usize,
+}
+
+// Duplicates Hook
+
+#[derive(Clone)]
+struct DuplicatesHookContext {
+    pub database: Arc<RwLock<ChunkedDatabase<Expedient>>>,
+    pub callback: Arc<Mutex<Box<dyn for<'r> FnMut(&Vec<DuplicateCluster<'r>>) + Send + Sync>>>,
+    pub options: DuplicatesHookOptions,
+    pub similarity_weights: Arc<RwLock<Database<SimilarityWeights>>>,
 }
 
 impl<'a> ExpedientDatabase<'a> {
@@ -107,26 +579,96 @@ impl<'a> ExpedientDatabase<'a> {
             HookId::ListExpedientOrders(id) => {
                 self.hook_pool.list_orders_observable.unsubscrive(id)
             }
-            HookId::ListFilter(id) => self.hook_pool.list_filter.unsubscrive(id),
+            HookId::Suggestions(id) => self.hook_pool.suggestions.unsubscrive(id),
+            HookId::SavedSearches(id) => self.hook_pool.saved_searches.unsubscrive(id),
+            HookId::SmartSearch(id) => self.hook_pool.smart_search.unsubscrive(id),
+            HookId::Duplicates(id) => self.hook_pool.duplicates.unsubscrive(id),
         }
     }
     pub fn release_all_hooks(&mut self) {
-        self.hook_pool = Default::default();
+        self.hook_pool = HookPool::new(
+            self.hook_pool.workers.clone(),
+            self.hook_pool.dispatch_window,
+        );
+    }
+    pub fn set_dispatch_window(&mut self, dispatch_window: Duration) {
+        self.hook_pool.dispatch_window = dispatch_window;
     }
     pub fn interrupt_dispatch(&mut self) {
         self.hook_pool.list_observable.stop_trigger();
         self.hook_pool.list_orders_observable.stop_trigger();
+        self.hook_pool.suggestions.stop_trigger();
+        self.hook_pool.smart_search.stop_trigger();
+        self.hook_pool.duplicates.stop_trigger();
+    }
+    /// Hooks of saved searches read again their options
+    pub fn dispatch_saved_searches_change(&mut self, name: &str) {
+        self.hook_pool.saved_searches.trigger();
+
+        let saved_searches = self.saved_searches.read().unwrap();
+        for context in self.hook_pool.list_orders_observable.contexts() {
+            if context.saved_search.as_deref() != Some(name) {
+                continue;
+            }
+            // A deleted search keeps listing its last options
+            if let Some(mut options) = saved_searches.data.read(name) {
+                options.prepare();
+                *context.options.write().unwrap() = options;
+                context.changes.lock().unwrap().append(ChangeSet::rescan());
+            }
+        }
+        drop(saved_searches);
+        self.hook_pool
+            .list_orders_observable
+            .retrigger_filtered_after(self.hook_pool.dispatch_window, |context| {
+                context.saved_search.as_deref() == Some(name)
+            });
     }
-    pub fn dispatch_change(&mut self) {
-        self.hook_pool.observable.trigger();
-        self.hook_pool.list_observable.trigger();
-        self.hook_pool.list_orders_observable.trigger();
+    pub fn dispatch_change(&mut self, changes: ChangeSet) {
+        self.hook_pool
+            .observable
+            .trigger_filtered(|context| changes.contains(context.expedient_id));
+
+        for context in self.hook_pool.list_observable.contexts() {
+            context.changes.lock().unwrap().append(changes.clone());
+        }
+        self.hook_pool
+            .list_observable
+            .trigger_after(self.hook_pool.dispatch_window);
+
+        for context in self.hook_pool.list_orders_observable.contexts() {
+            context.changes.lock().unwrap().append(changes.clone());
+        }
+        self.hook_pool
+            .list_orders_observable
+            .trigger_after(self.hook_pool.dispatch_window);
+
+        self.hook_pool
+            .suggestions
+            .trigger_after(self.hook_pool.dispatch_window);
+        self.hook_pool
+            .smart_search
+            .trigger_after(self.hook_pool.dispatch_window);
+        self.hook_pool
+            .duplicates
+            .trigger_after(self.hook_pool.dispatch_window);
     }
 
     pub fn hook_expedient(
         &mut self,
         id: Uid,
-        callback: impl for<'r> FnMut(Option<&'r Expedient>) -> () + Send + Sync + 'a,
+        mut callback: impl for<'r> FnMut(Option<&'r Expedient>) -> () + Send + Sync + 'a,
+    ) -> HookId {
+        self.hook_expedient_revision(id, move |expedient| {
+            callback(expedient.map(|(expedient, _)| expedient))
+        })
+    }
+
+    /// Like `hook_expedient`, but the expedient is sent with its revision
+    pub fn hook_expedient_revision(
+        &mut self,
+        id: Uid,
+        callback: impl for<'r> FnMut(Option<(&'r Expedient, u32)>) -> () + Send + Sync + 'a,
     ) -> HookId {
         HookId::Expedient(self.hook_pool.observable.subscrive(
             Callback::new(
@@ -138,30 +680,170 @@ impl<'a> ExpedientDatabase<'a> {
                 |context| {
                     let database = context.database.read().unwrap();
                     let expedient = database.read(context.expedient_id);
-                    (context.callback.lock().unwrap())(expedient);
+                    let revision = database.revision(context.expedient_id);
+                    (context.callback.lock().unwrap())(expedient.zip(revision));
                 },
             ),
             true,
         ))
     }
 
+    /// Amount of parts in which `len` expedients are scanned
+    fn partition_count(len: usize) -> usize {
+        (len / MIN_PARTITION_LEN).clamp(1, available_workers())
+    }
+
+    /// Scans each partition on its own thread. Results are returned in the order of the partitions.
+    fn scan_partitions<I, T>(
+        partitions: Vec<I>,
+        scan: impl Fn(I) -> Option<Vec<T>> + Sync,
+    ) -> Option<Vec<Vec<T>>>
+    where
+        I: Iterator + Send,
+        T: Send,
+    {
+        if partitions.len() <= 1 {
+            return partitions.into_iter().map(scan).collect();
+        }
+
+        std::thread::scope(|scope| {
+            let scan = &scan;
+            let threads: Vec<_> = partitions
+                .into_iter()
+                .map(|partition| scope.spawn(move || scan(partition)))
+                .collect();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().expect("Partition scan paniked"))
+                .collect()
+        })
+    }
+
+    /// Merges lists sorted by `compare` into one sorted list
+    fn merge_sorted<T>(lists: Vec<Vec<T>>, compare: impl Fn(&T, &T) -> Ordering) -> Vec<T> {
+        lists.into_iter().fold(vec![], |merged, list| {
+            let mut result = Vec::with_capacity(merged.len() + list.len());
+            let mut merged = merged.into_iter().peekable();
+            let mut list = list.into_iter().peekable();
+            loop {
+                let next = match (merged.peek(), list.peek()) {
+                    (Some(a), Some(b)) if compare(a, b) != Ordering::Greater => merged.next(),
+                    (_, Some(_)) => list.next(),
+                    (Some(_), None) => merged.next(),
+                    (None, None) => break,
+                };
+                result.extend(next);
+            }
+            result
+        })
+    }
+
+    fn merge_orders<'b>(
+        options: &ListOrdersHookOptions,
+        sorted_lists: Vec<Vec<(ListOrdersCursor, &'b Expedient)>>,
+    ) -> Vec<(ListOrdersCursor, &'b Expedient)> {
+        Self::merge_sorted(sorted_lists, |(a, _), (b, _)| {
+            a.cmp_sorted_by(b, &options.sort_by)
+        })
+    }
+
+    /// Filters the expedients of every partition in parallel and merges the sorted results
     fn list_orders<'b>(
         options: &ListOrdersHookOptions,
+        database: &'b ChunkedDatabase<Expedient>,
+        partitions: Vec<impl Iterator<Item = (Uid, &'b Expedient)> + Send>,
+        process: &AsyncCallbackProcess,
+        user_occurrences: &HashMap<String, i32>,
+    ) -> Option<Vec<(ListOrdersCursor, &'b Expedient)>> {
+        let sorted_lists = Self::scan_partitions(partitions, |expedients| {
+            Self::filter_orders(options, database, expedients, process, user_occurrences)
+        })?;
+        process.terminate_if_requested()?;
+
+        let list_orders = Self::merge_orders(options, sorted_lists);
+        process.terminate_if_requested()?;
+
+        Some(list_orders)
+    }
+
+    /// Partitions of the dynamic or the ancient expedients to scan.
+    /// When filtering by modification date, only the expedients modified inside the range are read.
+    fn orders_source<'b>(
+        options: &ListOrdersHookOptions,
+        database: &'b ChunkedDatabase<Expedient>,
+        ancient: bool,
+    ) -> Vec<Box<dyn Iterator<Item = (Uid, &'b Expedient)> + Send + 'b>> {
+        if options.modified_date.is_unbounded() {
+            if ancient {
+                let partitions = Self::partition_count(database.ancient_len());
+                Self::boxed_partitions(database.iter_ancient_partitions(partitions))
+            } else {
+                let partitions = Self::partition_count(database.dynamic_len());
+                Self::boxed_partitions(database.iter_partitions(partitions))
+            }
+        } else {
+            let ids: Vec<_> = database
+                .modified_between(options.modified_date.hashes())
+                .filter(|id| matches!(id, Uid::ANCIENT(_)) == ancient)
+                .collect();
+            let partitions = Self::partition_count(ids.len());
+            let partition_len = ((ids.len() + partitions - 1) / partitions).max(1);
+
+            ids.chunks(partition_len)
+                .map(|ids| -> Box<dyn Iterator<Item = _> + Send + 'b> {
+                    Box::new(
+                        ids.to_vec()
+                            .into_iter()
+                            .filter_map(|id| Some((id, database.read(id)?))),
+                    )
+                })
+                .collect()
+        }
+    }
+
+    fn boxed_partitions<'b>(
+        partitions: Vec<impl Iterator<Item = (Uid, &'b Expedient)> + Send + 'b>,
+    ) -> Vec<Box<dyn Iterator<Item = (Uid, &'b Expedient)> + Send + 'b>> {
+        partitions
+            .into_iter()
+            .map(|partition| -> Box<dyn Iterator<Item = _> + Send + 'b> { Box::new(partition) })
+            .collect()
+    }
+
+    fn filter_orders<'b>(
+        options: &ListOrdersHookOptions,
+        database: &ChunkedDatabase<Expedient>,
         expedients: impl Iterator<Item = (Uid, &'b Expedient)>,
-        concat_with: &mut Vec<(Uid, usize, &'b Expedient)>,
         process: &AsyncCallbackProcess,
         user_occurrences: &HashMap<String, i32>,
-    ) -> Option<Vec<(Uid, usize, &'b Expedient)>> {
+    ) -> Option<Vec<(ListOrdersCursor, &'b Expedient)>> {
         let mut filtered_expedients: Box<dyn Iterator<Item = _>> = Box::new(expedients);
         process.terminate_if_requested()?;
 
+        if !options.expedient_date.is_unbounded() {
+            let expedient_dates = options.expedient_date.hashes();
+            filtered_expedients = Box::new(
+                filtered_expedients
+                    .filter(move |(_, exp)| expedient_dates.contains(&exp.date.date_hash())),
+            );
+        }
+
+        if !options.modified_date.is_unbounded() {
+            let modified_dates = options.modified_date.hashes();
+            filtered_expedients = Box::new(filtered_expedients.filter(move |(id, _)| {
+                database
+                    .last_modified(*id)
+                    .map_or(false, |date| modified_dates.contains(&date.date_hash()))
+            }));
+        }
+
         if let Some(ref filter) = options.filter {
-            let car_code_filter = Filter::new(&filter.car_code.replace("_", "").replace(" ", ""));
+            let car_code_filter = Filter::new(license_plate::normalize(&filter.car_code));
 
             if filter.car_code != "" {
                 filtered_expedients = Box::new(filtered_expedients.filter(move |(_, exp)| {
-                    car_code_filter.test(&exp.license_plate.replace("_", "").replace(" ", "")) > 0
-                        || car_code_filter.test(&exp.vin.replace("_", "").replace(" ", "")) > 0
+                    car_code_filter.test(license_plate::normalize(&exp.license_plate)) > 0
+                        || car_code_filter.test(vin::normalize(&exp.vin)) > 0
                 }))
             }
         }
@@ -180,12 +862,14 @@ impl<'a> ExpedientDatabase<'a> {
 
         process.terminate_if_requested()?;
 
+        let order_dates = options.order_date.hashes();
         let mut orders: Box<dyn Iterator<Item = _>> = Box::new(
             filtered_expedients
                 .flat_map(|(id, exp)| (0..exp.orders.len()).map(move |index| (id, index, exp)))
                 .filter(|(_, index, expedient)| {
                     let order = &expedient.orders[*index];
                     order.date.date_hash() <= options.from_date.date_hash()
+                        && order_dates.contains(&order.date.date_hash())
                         && match order.state {
                             OrderState::Urgent => options.show_urgent,
                             OrderState::Todo => options.show_todo,
@@ -215,9 +899,6 @@ impl<'a> ExpedientDatabase<'a> {
         let mut list_orders: Vec<_> = orders.collect();
         process.terminate_if_requested()?;
 
-        list_orders.append(concat_with);
-        process.terminate_if_requested()?;
-
         if let Some(ref filter) = options.filter {
             if filter.popularity != 0 {
                 list_orders.retain(|(_, _, expedient)| {
@@ -234,77 +915,389 @@ impl<'a> ExpedientDatabase<'a> {
             }
         }
 
-        match options.sort_by {
-            ListOrdersHookOptionsSortBy::Newest => {
-                list_orders.sort_unstable_by_key(|(_, index, expedient)| {
-                    -expedient.orders[*index].date.date_hash()
-                })
-            }
-            ListOrdersHookOptionsSortBy::Oldest => {
-                list_orders.sort_unstable_by_key(|(_, index, expedient)| {
-                    expedient.orders[*index].date.date_hash()
-                })
-            }
-        };
+        let mut sorted_orders: Vec<_> = list_orders
+            .into_iter()
+            .map(|(id, index, expedient)| {
+                (
+                    ListOrdersCursor::new(&options.sort_by, id, index, expedient, database),
+                    expedient,
+                )
+            })
+            .collect();
+        process.terminate_if_requested()?;
 
+        sorted_orders.sort_unstable_by(|(a, _), (b, _)| a.cmp_sorted_by(b, &options.sort_by));
         process.terminate_if_requested()?;
 
-        list_orders.truncate(options.max_list_len);
+        Some(sorted_orders)
+    }
+
+    /// Range of a sorted list that is inside the page
+    fn page_range<T>(
+        options: &ListOrdersHookOptions,
+        cursor: Option<&ListOrdersCursor>,
+        sorted_list: &[T],
+        key: impl Fn(&T) -> &ListOrdersCursor,
+    ) -> Range<usize> {
+        let after_cursor = cursor.map_or(0, |cursor| {
+            sorted_list
+                .partition_point(|item| key(item).cmp_sorted_by(cursor, &options.sort_by).is_le())
+        });
+        let start = (after_cursor + options.offset).min(sorted_list.len());
+        let end = (start + options.max_list_len).min(sorted_list.len());
+        start..end
+    }
 
+    /// Facets of the whole list, `None` if the options don't ask for them
+    fn list_facets<'b, T>(
+        options: &ListOrdersHookOptions,
+        sorted_list: &[T],
+        key: impl Fn(&T) -> &ListOrdersCursor,
+        expedient: impl Fn(&T) -> Option<&'b Expedient>,
+    ) -> Option<ListOrdersFacets> {
+        if !options.facets {
+            return None;
+        }
+        Some(ListOrdersFacets::count(sorted_list.iter().filter_map(
+            |item| {
+                let key = key(item);
+                let expedient = expedient(item)?;
+                Some((expedient, expedient.orders.get(key.order_index)?))
+            },
+        )))
+    }
+
+    fn build_orders_page<'b, T>(
+        options: &ListOrdersHookOptions,
+        cursor: Option<&ListOrdersCursor>,
+        sorted_list: &[T],
+        key: impl Fn(&T) -> &ListOrdersCursor,
+        expedient: impl Fn(&T) -> Option<&'b Expedient>,
+        facets: Option<ListOrdersFacets>,
+    ) -> ListOrdersPage<'b> {
+        let range = Self::page_range(options, cursor, sorted_list, &key);
+        let page: Vec<_> = sorted_list[range.clone()]
+            .iter()
+            .filter_map(|item| {
+                let key = key(item);
+                let expedient = expedient(item)?;
+                if key.order_index < expedient.orders.len() {
+                    Some((key, expedient))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        ListOrdersPage {
+            next_cursor: if range.end < sorted_list.len() && !range.is_empty() {
+                Some(key(&sorted_list[range.end - 1]).clone())
+            } else {
+                None
+            },
+            groups: options.group_by.map_or(vec![], |group_by| {
+                Self::page_groups(group_by, &page, sorted_list, &key)
+            }),
+            orders: page
+                .iter()
+                .map(|(key, expedient)| (key.expedient_id, key.order_index, *expedient))
+                .collect(),
+            total_len: sorted_list.len(),
+            facets,
+        }
+    }
+
+    /// The list is sorted first by the group key, so each group is a run of orders
+    /// with the same first sort value
+    fn page_groups<T>(
+        group_by: ListOrdersSortKey,
+        page: &[(&ListOrdersCursor, &Expedient)],
+        sorted_list: &[T],
+        key: impl Fn(&T) -> &ListOrdersCursor,
+    ) -> Vec<ListOrdersGroup> {
+        let group_value = |cursor: &ListOrdersCursor| cursor.sort_values.first().cloned();
+
+        page.chunk_by(|(a, _), (b, _)| group_value(a) == group_value(b))
+            .map(|group| {
+                let (cursor, expedient) = group[0];
+                let value = group_value(cursor);
+                let before_group = |item: &T| {
+                    let item_value = group_value(key(item));
+                    if group_by.descending {
+                        item_value > value
+                    } else {
+                        item_value < value
+                    }
+                };
+                let start = sorted_list.partition_point(&before_group);
+                let end = sorted_list
+                    .partition_point(|item| before_group(item) || group_value(key(item)) == value);
+
+                ListOrdersGroup {
+                    header: group_by.field.header(
+                        expedient,
+                        &expedient.orders[cursor.order_index],
+                        &cursor.sort_values[0],
+                    ),
+                    page_len: group.len(),
+                    total_len: end - start,
+                }
+            })
+            .collect()
+    }
+
+    fn orders_page<'b>(
+        options: &ListOrdersHookOptions,
+        cursor: Option<&ListOrdersCursor>,
+        sorted_orders: &[(ListOrdersCursor, &'b Expedient)],
+    ) -> ListOrdersPage<'b> {
+        let facets = Self::list_facets(
+            options,
+            sorted_orders,
+            |(key, _)| key,
+            |(_, expedient)| Some(*expedient),
+        );
+        Self::build_orders_page(
+            options,
+            cursor,
+            sorted_orders,
+            |(key, _)| key,
+            |(_, expedient)| Some(*expedient),
+            facets,
+        )
+    }
+
+    fn orders_page_from_keys<'b>(
+        options: &ListOrdersHookOptions,
+        cursor: Option<&ListOrdersCursor>,
+        database: &'b ChunkedDatabase<Expedient>,
+        sorted_orders: &SortedOrders,
+    ) -> ListOrdersPage<'b> {
+        Self::build_orders_page(
+            options,
+            cursor,
+            &sorted_orders.keys,
+            |key| key,
+            |key| database.read(key.expedient_id),
+            sorted_orders.facets.clone(),
+        )
+    }
+
+    /// Keeps the keys of the sorted orders and counts their facets
+    fn sorted_orders(
+        options: &ListOrdersHookOptions,
+        database: &ChunkedDatabase<Expedient>,
+        keys: Vec<ListOrdersCursor>,
+    ) -> SortedOrders {
+        SortedOrders {
+            facets: Self::list_facets(
+                options,
+                &keys,
+                |key| key,
+                |key| database.read(key.expedient_id),
+            ),
+            keys,
+        }
+    }
+
+    /// Updates a sorted list of orders scanning only the expedients that have changed
+    fn update_sorted_orders(
+        options: &ListOrdersHookOptions,
+        database: &ChunkedDatabase<Expedient>,
+        sorted_orders: &mut Vec<ListOrdersCursor>,
+        changes: &ChangeSet,
+        process: &AsyncCallbackProcess,
+    ) -> Option<()> {
+        sorted_orders.retain(|key| !changes.contains(key.expedient_id));
         process.terminate_if_requested()?;
 
-        Some(list_orders)
+        let changed_ids = changes.changed();
+        let changed_expedients = changed_ids
+            .iter()
+            .filter_map(|id| Some((*id, database.read(*id)?)));
+
+        let changed_orders = Self::filter_orders(
+            options,
+            database,
+            changed_expedients,
+            process,
+            &HashMap::new(),
+        )?;
+
+        for (key, _) in changed_orders {
+            let position = sorted_orders
+                .partition_point(|other| other.cmp_sorted_by(&key, &options.sort_by).is_lt());
+            sorted_orders.insert(position, key);
+        }
+
+        process.terminate_if_requested()
+    }
+
+    /// Expedients of each user, only counted when filtering by popularity
+    fn user_occurrences(
+        options: &ListOrdersHookOptions,
+        database: &ChunkedDatabase<Expedient>,
+        process: &AsyncCallbackProcess,
+    ) -> Option<HashMap<String, i32>> {
+        // This way of counting user_occurrences can be improved
+        let mut user_occurrences = HashMap::<String, i32>::new();
+        if let Some(ref filter) = options.filter {
+            if filter.popularity != 0 {
+                for (_, expedient) in database.iter_all() {
+                    let mut username = expedient.user.to_lowercase();
+                    username.remove_matches(" ");
+                    *user_occurrences.entry(username).or_insert(1) += 1;
+                }
+                process.terminate_if_requested()?;
+            }
+        }
+        Some(user_occurrences)
+    }
+
+    /// Every order listed by the options, without pages, in the `sort_by` order
+    pub(super) fn query_orders(
+        mut options: ListOrdersHookOptions,
+        database: &ChunkedDatabase<Expedient>,
+    ) -> Vec<(Uid, usize)> {
+        options.prepare();
+        let process = AsyncCallbackProcess::uninterrupted();
+        let user_occurrences = Self::user_occurrences(&options, database, &process)
+            .expect("Uninterrupted process was terminated");
+
+        let lists = [false, true]
+            .into_iter()
+            .map(|ancient| {
+                Self::list_orders(
+                    &options,
+                    database,
+                    Self::orders_source(&options, database, ancient),
+                    &process,
+                    &user_occurrences,
+                )
+                .expect("Uninterrupted process was terminated")
+            })
+            .collect();
+        Self::merge_orders(&options, lists)
+            .into_iter()
+            .map(|(key, _)| (key.expedient_id, key.order_index))
+            .collect()
+    }
+
+    /// Sends to the hook the page that comes after the cursor.
+    /// The page is taken from the last result of the hook, so the filter is not recomputed.
+    /// Following updates of the hook will also use this cursor.
+    pub fn fetch_orders_page(&self, hook_id: HookId, cursor: Option<ListOrdersCursor>) {
+        if let HookId::ListExpedientOrders(id) = hook_id {
+            if let Some(hook) = self.hook_pool.list_orders_observable.get(id) {
+                let context = hook.context();
+                *context.cursor.lock().unwrap() = cursor.clone();
+
+                let database = context.database.read().unwrap();
+                // If there's no result, the running scan will send the page when it ends
+                if let Some(ref sorted_orders) = *context.sorted_orders.lock().unwrap() {
+                    (context.callback.lock().unwrap())(&Self::orders_page_from_keys(
+                        &context.options.read().unwrap(),
+                        cursor.as_ref(),
+                        &database,
+                        sorted_orders,
+                    ));
+                }
+            }
+        }
     }
 
     pub fn hook_list_orders(
+        &mut self,
+        options: ListOrdersHookOptions,
+        callback: impl for<'r> FnMut(&ListOrdersPage<'r>) -> () + Send + Sync + 'static,
+    ) -> HookId {
+        self.subscrive_list_orders(options, None, callback)
+    }
+
+    fn subscrive_list_orders(
         &mut self,
         mut options: ListOrdersHookOptions,
-        callback: impl for<'r> FnMut(&Vec<(Uid, usize, &'r Expedient)>) -> () + Send + Sync + 'a,
+        saved_search: Option<String>,
+        callback: impl for<'r> FnMut(&ListOrdersPage<'r>) -> () + Send + Sync + 'static,
     ) -> HookId {
-        if let Some(ref mut filter) = options.filter {
-            filter.to_lowercase();
-        }
+        options.prepare();
 
         HookId::ListExpedientOrders(self.hook_pool.list_orders_observable.subscrive(
             AsyncCallback::new(
                 ListOrdersHookContext {
                     database: self.database.clone(),
-                    options,
+                    cursor: Arc::new(Mutex::new(options.cursor.clone())),
+                    changes: Default::default(),
+                    sorted_orders: Default::default(),
+                    options: Arc::new(RwLock::new(options)),
+                    saved_search,
                     callback: Arc::new(Mutex::new(Box::new(callback))),
                 },
                 |context, process| {
+                    let changes = std::mem::take(&mut *context.changes.lock().unwrap());
+                    // If this scan is interrupted, the next one will start from scratch
+                    let last_result = context.sorted_orders.lock().unwrap().take();
+
+                    let options = context.options.read().unwrap().clone();
                     let database = context.database.read().unwrap();
 
-                    // This way of counting user_occurrences can be improved
-                    let mut user_occurrences = HashMap::<String, i32>::new();
-                    if let Some(ref filter) = context.options.filter {
-                        if filter.popularity != 0 {
-                            for (_, expedient) in database.iter_all() {
-                                let mut username = expedient.user.to_lowercase();
-                                username.remove_matches(" ");
-                                *user_occurrences.entry(username).or_insert(1) += 1;
-                            }
-                            process.terminate_if_requested()?;
+                    if let Some(last_result) = last_result {
+                        if !changes.rescan
+                            && changes.len() <= MAX_INCREMENTAL_CHANGES
+                            && !options.depends_on_all_expedients()
+                            && !options.has_relative_dates()
+                        {
+                            let mut keys = last_result.keys;
+                            Self::update_sorted_orders(
+                                &options, &database, &mut keys, &changes, &process,
+                            )?;
+                            let sorted_orders = Self::sorted_orders(&options, &database, keys);
+                            (context.callback.lock().unwrap())(&Self::orders_page_from_keys(
+                                &options,
+                                context.cursor.lock().unwrap().as_ref(),
+                                &database,
+                                &sorted_orders,
+                            ));
+                            *context.sorted_orders.lock().unwrap() = Some(sorted_orders);
+                            return Some(());
                         }
                     }
 
-                    let mut dynamic_list = Self::list_orders(
-                        &context.options,
-                        database.iter(),
-                        &mut vec![],
+                    let user_occurrences = Self::user_occurrences(&options, &database, &process)?;
+
+                    let dynamic_list = Self::list_orders(
+                        &options,
+                        &database,
+                        Self::orders_source(&options, &database, false),
                         &process,
                         &user_occurrences,
                     )?;
-                    (context.callback.lock().unwrap())(&dynamic_list);
-
-                    let full_list = Self::list_orders(
-                        &context.options,
-                        database.iter_ancient(),
-                        &mut dynamic_list,
+                    (context.callback.lock().unwrap())(&Self::orders_page(
+                        &options,
+                        context.cursor.lock().unwrap().as_ref(),
+                        &dynamic_list,
+                    ));
+
+                    let ancient_list = Self::list_orders(
+                        &options,
+                        &database,
+                        Self::orders_source(&options, &database, true),
                         &process,
                         &user_occurrences,
                     )?;
-                    (context.callback.lock().unwrap())(&full_list);
+                    let full_list = Self::merge_orders(&options, vec![dynamic_list, ancient_list]);
+                    let sorted_orders = Self::sorted_orders(
+                        &options,
+                        &database,
+                        full_list.iter().map(|(key, _)| key.clone()).collect(),
+                    );
+                    (context.callback.lock().unwrap())(&Self::orders_page_from_keys(
+                        &options,
+                        context.cursor.lock().unwrap().as_ref(),
+                        &database,
+                        &sorted_orders,
+                    ));
+                    *context.sorted_orders.lock().unwrap() = Some(sorted_orders);
 
                     Some(())
                 },
@@ -313,42 +1306,124 @@ impl<'a> ExpedientDatabase<'a> {
         ))
     }
 
+    /// Sends the names of the saved searches every time they change
+    pub fn hook_list_saved_searches(
+        &mut self,
+        callback: impl for<'r> FnMut(&Vec<&'r str>) -> () + Send + Sync + 'static,
+    ) -> HookId {
+        HookId::SavedSearches(self.hook_pool.saved_searches.subscrive(
+            Callback::new(
+                SavedSearchesHookContext {
+                    saved_searches: self.saved_searches.clone(),
+                    callback: Arc::new(Mutex::new(Box::new(callback))),
+                },
+                |context| {
+                    let saved_searches = context.saved_searches.read().unwrap();
+                    (context.callback.lock().unwrap())(&saved_searches.data.names().collect());
+                },
+            ),
+            true,
+        ))
+    }
+
+    /// Lists the orders of a saved search, `ListOrdersPage::total_len` is the amount of results.
+    /// The hook lists the search again with the new options when it's saved again.
+    pub fn hook_saved_search(
+        &mut self,
+        name: &str,
+        callback: impl for<'r> FnMut(&ListOrdersPage<'r>) -> () + Send + Sync + 'static,
+    ) -> Result<HookId> {
+        let options = match self.read_search(name) {
+            Some(options) => options,
+            None => return ErrorKind::NotFound.into(),
+        };
+        Ok(self.subscrive_list_orders(options, Some(name.into()), callback))
+    }
+
+    fn sort_by_similarity(list: &mut Vec<(Uid, f32)>) {
+        list.sort_unstable_by(|(id_a, a), (id_b, b)| {
+            b.partial_cmp(a)
+                .expect("Partial compare of f32 is None, data can not be sorted")
+                .then(id_a.cmp(id_b))
+        });
+    }
+
     pub fn hook_list_expedients(
         &mut self,
         options: ListExpedientsHookOptions,
-        callback: impl for<'r> FnMut(&Vec<(Uid, &'r Expedient, f32)>) -> () + Send + Sync + 'a,
+        callback: impl for<'r> FnMut(&ListExpedientsPage<'r>) -> () + Send + Sync + 'static,
     ) -> HookId {
         HookId::ListExpedients(self.hook_pool.list_observable.subscrive(
             AsyncCallback::new(
                 ListExpedientsHookContext {
                     database: self.database.clone(),
                     options,
+                    similarity_weights: self.similarity_weights.clone(),
+                    changes: Default::default(),
+                    sorted_expedients: Default::default(),
                     callback: Arc::new(Mutex::new(Box::new(callback))),
                 },
                 |context, process| {
-                    let database = context.database.read().unwrap();
+                    let changes = std::mem::take(&mut *context.changes.lock().unwrap());
+                    // If this scan is interrupted, the next one will start from scratch
+                    let last_result = context.sorted_expedients.lock().unwrap().take();
 
-                    let mut list: Vec<_> = database
-                        .iter()
-                        .map(|(id, expedient)| {
-                            (id, expedient, expedient.similarity(&context.options.filter))
-                        })
-                        .filter(|(_, _, similarity)| *similarity > 0.)
-                        .collect();
+                    let database = context.database.read().unwrap();
+                    let weights = context.similarity_weights.read().unwrap().data;
+                    let similarity = |(id, expedient): (Uid, &Expedient)| {
+                        (
+                            id,
+                            expedient.weighted_similarity(&context.options.filter, &weights),
+                        )
+                    };
+
+                    let list = match last_result {
+                        Some(mut list)
+                            if !changes.rescan && changes.len() <= MAX_INCREMENTAL_CHANGES =>
+                        {
+                            list.retain(|(id, _)| !changes.contains(*id));
+                            list.extend(
+                                changes
+                                    .changed()
+                                    .into_iter()
+                                    .filter(|id| matches!(id, Uid::DYNAMIC(_)))
+                                    .filter_map(|id| Some((id, database.read(id)?)))
+                                    .map(similarity)
+                                    .filter(|(_, similarity)| *similarity > 0.),
+                            );
+                            Self::sort_by_similarity(&mut list);
+                            list
+                        }
+                        _ => {
+                            let mut list: Vec<_> = database
+                                .iter()
+                                .map(similarity)
+                                .filter(|(_, similarity)| *similarity > 0.)
+                                .collect();
 
-                    process.terminate_if_requested()?;
+                            process.terminate_if_requested()?;
 
-                    // Sort by similarity
-                    list.sort_unstable_by(|(_, _, a), (_, _, b)| {
-                        b.partial_cmp(a)
-                            .expect("Partial compare of f32 is None, data can not be sorted")
-                    });
+                            Self::sort_by_similarity(&mut list);
+                            list
+                        }
+                    };
 
-                    list.truncate(context.options.max_list_len);
+                    let total_len = list.len();
+                    let start = context.options.offset.min(total_len);
+                    let end = (start + context.options.max_list_len).min(total_len);
 
                     process.terminate_if_requested()?;
 
-                    (context.callback.lock().unwrap())(&list);
+                    (context.callback.lock().unwrap())(&ListExpedientsPage {
+                        expedients: list[start..end]
+                            .iter()
+                            .filter_map(|(id, similarity)| {
+                                Some((*id, database.read(*id)?, *similarity))
+                            })
+                            .collect(),
+                        total_len,
+                    });
+                    *context.sorted_expedients.lock().unwrap() = Some(list);
 
                     // TODO: check on ancient database
 
@@ -359,80 +1434,47 @@ impl<'a> ExpedientDatabase<'a> {
         ))
     }
 
-    fn list_filter<'b>(
-        filter: &Filter,
-        expedients: impl Iterator<Item = (&'b str, UtcDate)>,
-        concat_with: &mut Vec<(&'b str, UtcDate)>,
-        process: &AsyncCallbackProcess,
-    ) -> Option<Vec<(&'b str, UtcDate)>> {
-        const MAX_LIST_LEN: usize = 5;
-
-        process.terminate_if_requested()?;
-
-        let mut list: Vec<_> = expedients
-            .map(|(data, date)| (data.trim(), date))
-            .filter(|(data, _)| !data.is_empty() && filter.test(data) > 0)
-            .collect();
-
-        process.terminate_if_requested()?;
-
-        list.append(concat_with);
-
-        process.terminate_if_requested()?;
-
-        // Eliminate duplicates
-        list.sort_unstable_by_key(|(text, _)| *text);
-        list.dedup_by_key(|(text, _)| *text);
-
-        process.terminate_if_requested()?;
-
-        list.sort_unstable_by_key(|(data, date)| (-(filter.test(data) as i32), -date.date_hash()));
-        list.truncate(MAX_LIST_LEN);
-
-        process.terminate_if_requested()?;
-
-        Some(list)
-    }
-
-    pub fn hook_list_users(
+    /// Searches at once expedients, orders and customers, first on the recent expedients
+    /// and then on all of them. Which fields are searched depends on how the query looks.
+    pub fn hook_smart_search(
         &mut self,
-        filter: String,
-        callback: impl for<'r> FnMut(&Vec<&str>) -> () + Send + Sync + 'a,
+        query: String,
+        max_list_len: usize,
+        callback: impl for<'r> FnMut(&SmartSearchPage<'r>) -> () + Send + Sync + 'static,
     ) -> HookId {
-        HookId::ListFilter(self.hook_pool.list_filter.subscrive(
+        HookId::SmartSearch(self.hook_pool.smart_search.subscrive(
             AsyncCallback::new(
-                ListFilterHookContext {
+                SmartSearchHookContext {
                     database: self.database.clone(),
-                    filter,
                     callback: Arc::new(Mutex::new(Box::new(callback))),
+                    query,
+                    max_list_len,
                 },
                 |context, process| {
-                    let user_filter = Filter::new(&context.filter);
+                    let search = SmartSearch::new(&context.query);
                     let database = context.database.read().unwrap();
-
-                    let mut dynamic_list = Self::list_filter(
-                        &user_filter,
-                        database.iter().map(|(_, expedient)| {
-                            (expedient.user.as_str(), expedient.newest_date())
-                        }),
-                        &mut vec![],
+                    let mut results = Self::smart_search_scan(
+                        &search,
+                        database.iter_partitions(Self::partition_count(database.dynamic_len())),
                         &process,
                     )?;
-                    (context.callback.lock().unwrap())(
-                        &dynamic_list.iter().map(|(data, _)| *data).collect(),
-                    );
+                    process.terminate_if_requested()?;
+                    (context.callback.lock().unwrap())(&SmartSearchPage {
+                        kind: search.kind(),
+                        results: SmartSearch::rank(results.clone(), context.max_list_len),
+                    });
 
-                    let full_list = Self::list_filter(
-                        &user_filter,
-                        database.iter_ancient().map(|(_, expedient)| {
-                            (expedient.user.as_str(), expedient.newest_date())
-                        }),
-                        &mut dynamic_list,
+                    results.append(&mut Self::smart_search_scan(
+                        &search,
+                        database
+                            .iter_ancient_partitions(Self::partition_count(database.ancient_len())),
                         &process,
-                    )?;
-                    (context.callback.lock().unwrap())(
-                        &full_list.iter().map(|(data, _)| *data).collect(),
-                    );
+                    )?);
+                    process.terminate_if_requested()?;
+                    (context.callback.lock().unwrap())(&SmartSearchPage {
+                        kind: search.kind(),
+                        results: SmartSearch::rank(results, context.max_list_len),
+                    });
 
                     Some(())
                 },
@@ -441,45 +1483,54 @@ impl<'a> ExpedientDatabase<'a> {
         ))
     }
 
-    pub fn hook_list_models(
+    fn smart_search_scan<'b>(
+        search: &SmartSearch,
+        partitions: Vec<impl Iterator<Item = (Uid, &'b Expedient)> + Send>,
+        process: &AsyncCallbackProcess,
+    ) -> Option<Vec<SmartSearchResult<'b>>> {
+        Some(
+            Self::scan_partitions(partitions, |expedients| {
+                process.terminate_if_requested()?;
+                Some(
+                    expedients
+                        .flat_map(|(id, expedient)| search.matches(id, expedient))
+                        .collect(),
+                )
+            })?
+            .into_iter()
+            .flatten()
+            .collect(),
+        )
+    }
+
+    /// Lists the clusters of expedients that refer to the same vehicle,
+    /// computed again when any expedient changes
+    pub fn hook_duplicates(
         &mut self,
-        filter: String,
-        callback: impl for<'r> FnMut(&Vec<&str>) -> () + Send + Sync + 'a,
+        options: DuplicatesHookOptions,
+        callback: impl for<'r> FnMut(&Vec<DuplicateCluster<'r>>) -> () + Send + Sync + 'static,
     ) -> HookId {
-        HookId::ListFilter(self.hook_pool.list_filter.subscrive(
+        HookId::Duplicates(self.hook_pool.duplicates.subscrive(
             AsyncCallback::new(
-                ListFilterHookContext {
+                DuplicatesHookContext {
                     database: self.database.clone(),
-                    filter,
                     callback: Arc::new(Mutex::new(Box::new(callback))),
+                    options,
+                    similarity_weights: self.similarity_weights.clone(),
                 },
                 |context, process| {
-                    let model_filter = Filter::new(&context.filter);
                     let database = context.database.read().unwrap();
-
-                    let mut dynamic_list = Self::list_filter(
-                        &model_filter,
-                        database.iter().map(|(_, expedient)| {
-                            (expedient.model.as_str(), expedient.newest_date())
-                        }),
-                        &mut vec![],
+                    let weights = context.similarity_weights.read().unwrap().data;
+                    let clusters = find_duplicates(
+                        database.iter_all().collect(),
+                        &context.options,
+                        &weights,
                         &process,
                     )?;
-                    (context.callback.lock().unwrap())(
-                        &dynamic_list.iter().map(|(data, _)| *data).collect(),
-                    );
 
-                    let full_list = Self::list_filter(
-                        &model_filter,
-                        database.iter_ancient().map(|(_, expedient)| {
-                            (expedient.model.as_str(), expedient.newest_date())
-                        }),
-                        &mut dynamic_list,
-                        &process,
-                    )?;
-                    (context.callback.lock().unwrap())(
-                        &full_list.iter().map(|(data, _)| *data).collect(),
-                    );
+                    process.terminate_if_requested()?;
+
+                    (context.callback.lock().unwrap())(&clusters);
 
                     Some(())
                 },
@@ -488,45 +1539,50 @@ impl<'a> ExpedientDatabase<'a> {
         ))
     }
 
-    pub fn hook_list_license_plates(
+    /// Suggests values already used on a field, the most used first
+    pub fn hook_suggestions(
         &mut self,
-        filter: String,
-        callback: impl for<'r> FnMut(&Vec<&str>) -> () + Send + Sync + 'a,
+        options: SuggestionsHookOptions,
+        callback: impl for<'r> FnMut(&Vec<&str>) -> () + Send + Sync + 'static,
     ) -> HookId {
-        HookId::ListFilter(self.hook_pool.list_filter.subscrive(
+        HookId::Suggestions(self.hook_pool.suggestions.subscrive(
             AsyncCallback::new(
-                ListFilterHookContext {
+                SuggestionsHookContext {
                     database: self.database.clone(),
-                    filter,
+                    options,
                     callback: Arc::new(Mutex::new(Box::new(callback))),
                 },
                 |context, process| {
-                    let license_plate_filter = Filter::new(&context.filter);
+                    let filter = Filter::new(&context.options.filter);
                     let database = context.database.read().unwrap();
+                    let mut counts = HashMap::new();
 
-                    let mut dynamic_list = Self::list_filter(
-                        &license_plate_filter,
-                        database.iter().map(|(_, expedient)| {
-                            (expedient.license_plate.as_str(), expedient.newest_date())
-                        }),
-                        &mut vec![],
+                    Self::count_suggestions(
+                        &filter,
+                        context.options.field,
+                        database.iter_partitions(Self::partition_count(database.dynamic_len())),
+                        &mut counts,
                         &process,
                     )?;
-                    (context.callback.lock().unwrap())(
-                        &dynamic_list.iter().map(|(data, _)| *data).collect(),
-                    );
-
-                    let full_list = Self::list_filter(
-                        &license_plate_filter,
-                        database.iter_ancient().map(|(_, expedient)| {
-                            (expedient.license_plate.as_str(), expedient.newest_date())
-                        }),
-                        &mut dynamic_list,
+                    (context.callback.lock().unwrap())(&Self::rank_suggestions(
+                        &filter,
+                        &counts,
+                        context.options.max_list_len,
+                    ));
+
+                    Self::count_suggestions(
+                        &filter,
+                        context.options.field,
+                        database
+                            .iter_ancient_partitions(Self::partition_count(database.ancient_len())),
+                        &mut counts,
                         &process,
                     )?;
-                    (context.callback.lock().unwrap())(
-                        &full_list.iter().map(|(data, _)| *data).collect(),
-                    );
+                    (context.callback.lock().unwrap())(&Self::rank_suggestions(
+                        &filter,
+                        &counts,
+                        context.options.max_list_len,
+                    ));
 
                     Some(())
                 },
@@ -535,101 +1591,99 @@ impl<'a> ExpedientDatabase<'a> {
         ))
     }
 
-    pub fn hook_list_vins(
-        &mut self,
-        filter: String,
-        callback: impl for<'r> FnMut(&Vec<&str>) -> () + Send + Sync + 'a,
-    ) -> HookId {
-        HookId::ListFilter(self.hook_pool.list_filter.subscrive(
-            AsyncCallback::new(
-                ListFilterHookContext {
-                    database: self.database.clone(),
-                    filter,
-                    callback: Arc::new(Mutex::new(Box::new(callback))),
-                },
-                |context, process| {
-                    let vin_filter = Filter::new(&context.filter);
-                    let database = context.database.read().unwrap();
+    /// Scans in parallel the values of `field` of every partition,
+    /// counting how many times each one is used and when it was used last
+    fn count_suggestions<'b>(
+        filter: &Filter,
+        field: SuggestionField,
+        partitions: Vec<impl Iterator<Item = (Uid, &'b Expedient)> + Send>,
+        counts: &mut HashMap<&'b str, (usize, UtcDate)>,
+        process: &AsyncCallbackProcess,
+    ) -> Option<()> {
+        process.terminate_if_requested()?;
 
-                    let mut dynamic_list = Self::list_filter(
-                        &vin_filter,
-                        database.iter().map(|(_, expedient)| {
-                            (expedient.vin.as_str(), expedient.newest_date())
-                        }),
-                        &mut vec![],
-                        &process,
-                    )?;
-                    (context.callback.lock().unwrap())(
-                        &dynamic_list.iter().map(|(data, _)| *data).collect(),
-                    );
+        let lists = Self::scan_partitions(partitions, |expedients| {
+            process.terminate_if_requested()?;
+            Some(
+                expedients
+                    .flat_map(|(_, expedient)| field.values(expedient))
+                    .map(|(data, date)| (data.trim(), date))
+                    .filter(|(data, _)| !data.is_empty() && filter.test(data) > 0)
+                    .collect::<Vec<_>>(),
+            )
+        })?;
 
-                    let full_list = Self::list_filter(
-                        &vin_filter,
-                        database.iter_ancient().map(|(_, expedient)| {
-                            (expedient.vin.as_str(), expedient.newest_date())
-                        }),
-                        &mut dynamic_list,
-                        &process,
-                    )?;
-                    (context.callback.lock().unwrap())(
-                        &full_list.iter().map(|(data, _)| *data).collect(),
-                    );
+        process.terminate_if_requested()?;
 
-                    Some(())
-                },
-            ),
-            true,
-        ))
-    }
+        for (data, date) in lists.into_iter().flatten() {
+            let (count, last_date) = counts.entry(data).or_insert((0, date));
+            *count += 1;
+            if date.date_hash() > last_date.date_hash() {
+                *last_date = date;
+            }
+        }
 
-    pub fn hook_list_order_titles(
-        &mut self,
-        filter: String,
-        callback: impl for<'r> FnMut(&Vec<&str>) -> () + Send + Sync + 'a,
-    ) -> HookId {
-        HookId::ListFilter(self.hook_pool.list_filter.subscrive(
-            AsyncCallback::new(
-                ListFilterHookContext {
-                    database: self.database.clone(),
-                    filter,
-                    callback: Arc::new(Mutex::new(Box::new(callback))),
-                },
-                |context, process| {
-                    let order_title_filter = Filter::new(&context.filter);
-                    let database = context.database.read().unwrap();
+        Some(())
+    }
 
-                    let mut dynamic_list = Self::list_filter(
-                        &order_title_filter,
-                        database.iter().flat_map(|(_, exp)| {
-                            exp.orders
-                                .iter()
-                                .map(|order| (order.title.as_str(), order.date))
-                        }),
-                        &mut vec![],
-                        &process,
-                    )?;
-                    (context.callback.lock().unwrap())(
-                        &dynamic_list.iter().map(|(data, _)| *data).collect(),
-                    );
+    /// Best matches first, then the most used and then the most recent
+    fn rank_suggestions<'b>(
+        filter: &Filter,
+        counts: &HashMap<&'b str, (usize, UtcDate)>,
+        max_list_len: usize,
+    ) -> Vec<&'b str> {
+        let mut list: Vec<_> = counts.iter().collect();
+        list.sort_unstable_by_key(|(data, (count, date))| {
+            (
+                -(filter.test(data) as i64),
+                -(*count as i64),
+                -date.date_hash(),
+                **data,
+            )
+        });
+        list.into_iter()
+            .take(max_list_len)
+            .map(|(data, _)| *data)
+            .collect()
+    }
+}
 
-                    let full_list = Self::list_filter(
-                        &order_title_filter,
-                        database.iter_ancient().flat_map(|(_, exp)| {
-                            exp.orders
-                                .iter()
-                                .map(|order| (order.title.as_str(), order.date))
-                        }),
-                        &mut dynamic_list,
-                        &process,
-                    )?;
-                    (context.callback.lock().unwrap())(
-                        &full_list.iter().map(|(data, _)| *data).collect(),
-                    );
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scan_partitions_and_merge_sorted() {
+        let numbers: Vec<i32> = (0..100).map(|n| (n * 37) % 101).collect();
+        let partitions: Vec<_> = numbers.chunks(30).map(|chunk| chunk.iter()).collect();
+
+        let sorted_lists = ExpedientDatabase::scan_partitions(partitions, |numbers| {
+            let mut list: Vec<_> = numbers.copied().collect();
+            list.sort_unstable_by(|a, b| b.cmp(a));
+            Some(list)
+        })
+        .unwrap();
+        assert_eq!(4, sorted_lists.len());
+
+        let mut expected = numbers.clone();
+        expected.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(
+            expected,
+            ExpedientDatabase::merge_sorted(sorted_lists, |a, b| b.cmp(a))
+        );
+    }
 
-                    Some(())
-                },
-            ),
-            true,
-        ))
+    #[test]
+    fn cancelled_partition_cancels_scan() {
+        let partitions = vec![0..10, 10..20, 20..30];
+        let process = AsyncCallbackProcess::uninterrupted();
+        let result = ExpedientDatabase::scan_partitions(partitions, |numbers| {
+            if numbers.start == 10 {
+                None
+            } else {
+                Some(numbers.collect::<Vec<_>>())
+            }
+        });
+        assert!(result.is_none());
     }
 }
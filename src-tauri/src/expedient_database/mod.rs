@@ -1,20 +1,48 @@
+mod bulk_edit;
+mod change_set;
+mod duplicates;
 mod expedient;
+mod field_suggestions;
 mod filter;
+mod history;
 mod hooks;
 mod restore_data_from_arxivador;
+mod revisions;
+mod saved_searches;
+mod similarity_weights;
+mod smart_search;
+mod split;
 mod statistics;
+mod transaction;
+mod validation;
 use crate::chunked_database::*;
 pub use crate::collections::UtcDate;
+use crate::database::Database;
 use crate::error::*;
+use crate::license_plate;
+pub use bulk_edit::*;
+pub use change_set::*;
+pub use duplicates::*;
 pub use expedient::*;
+pub use field_suggestions::*;
+use history::*;
 pub use hooks::*;
 use restore_data_from_arxivador::*;
+pub use revisions::*;
+pub use saved_searches::*;
 use serde::{Deserialize, Serialize};
+pub use smart_search::*;
+pub use split::*;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
+pub use transaction::*;
+pub use validation::*;
 
 pub struct ExpedientDatabase<'a> {
     database: Arc<RwLock<ChunkedDatabase<Expedient>>>,
+    saved_searches: Arc<RwLock<Database<SavedSearches>>>,
+    similarity_weights: Arc<RwLock<Database<SimilarityWeights>>>,
+    history: History,
     hook_pool: HookPool<'a>,
 }
 
@@ -27,6 +55,13 @@ impl<'a> ExpedientDatabase<'a> {
                 path,
                 CHUNKED_DATABASE_DYNAMIC_SIZE,
             )?)),
+            saved_searches: Arc::new(RwLock::new(Database::open_or_create(
+                &path.join("saved_searches"),
+            )?)),
+            similarity_weights: Arc::new(RwLock::new(Database::open_or_create(
+                &path.join("similarity_weights"),
+            )?)),
+            history: Default::default(),
             hook_pool: Default::default(),
         })
     }
@@ -37,6 +72,11 @@ impl<'a> ExpedientDatabase<'a> {
                 path,
                 CHUNKED_DATABASE_DYNAMIC_SIZE,
             )?)),
+            saved_searches: Arc::new(RwLock::new(Database::create(&path.join("saved_searches"))?)),
+            similarity_weights: Arc::new(RwLock::new(Database::create(
+                &path.join("similarity_weights"),
+            )?)),
+            history: Default::default(),
             hook_pool: Default::default(),
         })
     }
@@ -51,6 +91,13 @@ impl<'a> ExpedientDatabase<'a> {
                 path,
                 CHUNKED_DATABASE_DYNAMIC_SIZE,
             )?)),
+            saved_searches: Arc::new(RwLock::new(Database::open_or_create(
+                &path.join("saved_searches"),
+            )?)),
+            similarity_weights: Arc::new(RwLock::new(Database::open_or_create(
+                &path.join("similarity_weights"),
+            )?)),
+            history: Default::default(),
             hook_pool: Default::default(),
         })
     }
@@ -81,7 +128,6 @@ impl<'a> ExpedientDatabase<'a> {
         let mut idx_b = 0;
 
         let mut repeated = 0;
-        println!(">>>> Not Equal:");
 
         while idx_a < ids_a.len() && idx_b < ids_b.len() {
             let a = &ids_a[idx_a];
@@ -90,16 +136,7 @@ impl<'a> ExpedientDatabase<'a> {
             if a.0 == b.0 {
                 idx_a += 1;
                 idx_b += 1;
-                if a.1 != b.1 {
-                    if a.1.vin.is_empty() || b.1.vin.is_empty() {
-                        println!(
-                            "{}  {}    {}  {}",
-                            a.1.vin, a.1.license_plate, b.1.vin, b.1.license_plate
-                        );
-                    } else {
-                        println!("{}   {}", a.1.vin, b.1.vin);
-                    }
-                } else {
+                if a.1 == b.1 {
                     db.delete(Uid::ANCIENT(a.0));
                     repeated += 1;
                 }
@@ -109,7 +146,6 @@ impl<'a> ExpedientDatabase<'a> {
                 idx_b += 1;
             }
         }
-        println!("---------------");
 
         repeated
     }
@@ -131,33 +167,123 @@ impl<'a> ExpedientDatabase<'a> {
             .map(|exp| exp.clone())
     }
 
-    pub fn update_expedient(&mut self, id: Uid, expedient: Expedient) {
+    pub fn update_expedient(&mut self, id: Uid, mut expedient: Expedient) {
+        canonicalize_license_plate(&mut expedient);
         self.interrupt_dispatch();
-        self.database.write().unwrap().update(id, expedient);
-        self.dispatch_change();
+        {
+            let mut database = self.database.write().unwrap();
+            if let Some(before) = database.read(id).cloned() {
+                self.history.record(vec![Mutation::new(
+                    id,
+                    Some(before),
+                    Some(expedient.clone()),
+                )]);
+            }
+            database.update(id, expedient);
+        }
+        self.dispatch_change(ChangeSet::updated(id));
+    }
+    /// Applies all the patches to the expedient on `revision`, or none if one of them fails.
+    /// Fails with `Conflict` if the stored expedient is not on `revision`,
+    /// and with `InvalidInput` if a patch has an index out of range.
+    pub fn patch_expedient(
+        &mut self,
+        id: Uid,
+        revision: u32,
+        patches: Vec<ExpedientPatch>,
+        mode: ValidationMode,
+    ) -> Result<ValidatedWrite> {
+        let mut expedient = match self.read_expedient(id) {
+            Some(expedient) => expedient,
+            None => return ErrorKind::NotFound.into(),
+        };
+        if self.expedient_revision(id) != Some(revision) {
+            return ErrorKind::Conflict.into();
+        }
+        if !expedient.apply_patches(patches) {
+            return ErrorKind::InvalidInput.into();
+        }
+        self.update_validated_expedient(id, Some(revision), expedient, mode)
+    }
+    /// Moves the order to the expedient `to`, where it's placed by its date.
+    /// Both expedients are written at once. Returns the new index of the order.
+    pub fn move_order(&mut self, from: Uid, order_index: usize, to: Uid) -> Result<usize> {
+        let (mut from_expedient, mut to_expedient) = {
+            let database = self.database.read().unwrap();
+            match (database.read(from), database.read(to)) {
+                (Some(from_expedient), Some(to_expedient)) => {
+                    (from_expedient.clone(), to_expedient.clone())
+                }
+                _ => return ErrorKind::NotFound.into(),
+            }
+        };
+        if order_index >= from_expedient.orders.len() {
+            return ErrorKind::InvalidInput.into();
+        }
+        if from == to {
+            return Ok(order_index);
+        }
+
+        let order = from_expedient.orders.remove(order_index);
+        let new_index = to_expedient
+            .orders
+            .partition_point(|other| other.date.date_hash() <= order.date.date_hash());
+        to_expedient.orders.insert(new_index, order);
+
+        let mut transaction = Transaction::new();
+        transaction
+            .update(from, from_expedient)
+            .update(to, to_expedient);
+        self.commit_accepted(transaction)?;
+        Ok(new_index)
     }
-    pub fn create_expedient(&mut self, expedient: Expedient) -> Uid {
+    pub fn create_expedient(&mut self, mut expedient: Expedient) -> Uid {
+        canonicalize_license_plate(&mut expedient);
         self.interrupt_dispatch();
-        let id = self.database.write().unwrap().push(expedient);
-        self.dispatch_change();
+        let id = self.database.write().unwrap().push(expedient.clone());
+        self.history
+            .record(vec![Mutation::new(id, None, Some(expedient))]);
+        self.dispatch_change(ChangeSet::created(id));
         id
     }
     pub fn delete_expedient(&mut self, id: Uid) {
         self.interrupt_dispatch();
-        self.database.write().unwrap().delete(id);
-        self.dispatch_change();
+        {
+            let mut database = self.database.write().unwrap();
+            if let Some(before) = database.read(id).cloned() {
+                self.history
+                    .record(vec![Mutation::new(id, Some(before), None)]);
+            }
+            database.delete(id);
+        }
+        self.dispatch_change(ChangeSet::deleted(id));
     }
 
     pub fn save(&mut self) -> Result<()> {
-        self.database.write().unwrap().save()?;
+        let moved_items = self.database.write().unwrap().save()?;
+        if moved_items > 0 {
+            // Moved expedients have a new Uid
+            self.history.clear();
+            self.interrupt_dispatch();
+            self.dispatch_change(ChangeSet::rescan());
+        }
         Ok(())
     }
 }
 
+/// Stores the license plate on its canonical form, unrecognised plates are left as written
+fn canonicalize_license_plate(expedient: &mut Expedient) {
+    if let Some(canonical) = license_plate::canonical(&expedient.license_plate) {
+        expedient.license_plate = canonical;
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::test_utils::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
 
     #[test]
     fn create_close_and_open_database() {
@@ -385,7 +511,8 @@ mod test {
             date: UtcDate::ymdh(2921, 4, 2, 11),
         };
 
-        let mut hook_has_triggered = false;
+        let hook_has_triggered = Arc::new(AtomicBool::new(false));
+        let hook_triggered = hook_has_triggered.clone();
 
         let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
         db.create_expedient(expedient_0);
@@ -397,9 +524,11 @@ mod test {
             ListExpedientsHookOptions {
                 filter: expedient_filter,
                 max_list_len: 10,
+                offset: 0,
             },
-            |filter| {
-                hook_has_triggered = true;
+            move |page| {
+                let filter = &page.expedients;
+                hook_triggered.store(true, Ordering::SeqCst);
                 assert_eq!(3, filter.len());
 
                 assert_eq!(id_2, filter[0].0);
@@ -415,70 +544,1923 @@ mod test {
         sleep_for(50);
         drop(db);
 
-        assert!(hook_has_triggered);
+        assert!(hook_has_triggered.load(Ordering::SeqCst));
     }
 
     #[test]
-    fn save_do_not_create_files_when_data_is_not_changed() {
+    fn list_orders_hook_pages() {
         let tempdir = TempDir::new();
+
+        let order = |day, state| Order {
+            date: UtcDate::ymdh(2022, 3, day, 9),
+            title: "".into(),
+            description: "".into(),
+            state,
+        };
+        let expedient = |orders| Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "".into(),
+            orders,
+            user: "".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 3, 1, 9),
+        };
+
+        let pages = Arc::new(std::sync::Mutex::new(vec![]));
+
         {
-            let mut database = ExpedientDatabase::create(&tempdir.path).unwrap();
-            database.save().unwrap();
-            database.create_expedient(Expedient {
-                description: "Pedro".into(),
-                license_plate: "".into(),
-                model: "Car".into(),
-                orders: vec![],
-                user: "".into(),
-                vin: "".into(),
-                date: UtcDate::ymdh(2921, 4, 3, 11),
-            });
-            database.save().unwrap();
-            sleep_for(1100);
-            database.save().unwrap();
-            database.save().unwrap();
+            let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+            let id_a = db.create_expedient(expedient(vec![
+                order(1, OrderState::Done),
+                order(3, OrderState::Todo),
+            ]));
+            let id_b = db.create_expedient(expedient(vec![order(2, OrderState::Urgent)]));
+            let id_c = db.create_expedient(expedient(vec![order(4, OrderState::Awaiting)]));
+
+            let hook_pages = pages.clone();
+            let hook_id = db.hook_list_orders(
+                ListOrdersHookOptions {
+                    filter: None,
+                    sort_by: ListOrdersHookOptionsSortBy::Newest,
+                    max_list_len: 2,
+                    from_date: UtcDate::ymdh(2023, 1, 1, 0),
+                    show_urgent: true,
+                    show_todo: true,
+                    show_awaiting: true,
+                    show_instore: true,
+                    show_done: true,
+                    cursor: None,
+                    offset: 0,
+                    group_by: None,
+                    facets: false,
+                    order_date: Default::default(),
+                    expedient_date: Default::default(),
+                    modified_date: Default::default(),
+                },
+                move |page| {
+                    hook_pages.lock().unwrap().push((
+                        page.orders
+                            .iter()
+                            .map(|(id, index, _)| (*id, *index))
+                            .collect::<Vec<_>>(),
+                        page.total_len,
+                        page.next_cursor.clone(),
+                    ))
+                },
+            );
+            sleep_for(50);
+
+            let (first_page, total_len, next_cursor) = pages.lock().unwrap().pop().unwrap();
+            assert_eq!(vec![(id_c, 0), (id_a, 1)], first_page);
+            assert_eq!(4, total_len);
+            assert!(next_cursor.is_some());
+
+            db.fetch_orders_page(hook_id, next_cursor);
+
+            let (second_page, total_len, next_cursor) = pages.lock().unwrap().pop().unwrap();
+            assert_eq!(vec![(id_b, 0), (id_a, 0)], second_page);
+            assert_eq!(4, total_len);
+            assert_eq!(None, next_cursor);
         }
+    }
 
-        let year = crate::database::Instant::now().year().to_string();
+    #[test]
+    fn list_orders_hook_updates_with_changes() {
+        let tempdir = TempDir::new();
+
+        let expedient = |day, state| Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "".into(),
+            orders: vec![Order {
+                date: UtcDate::ymdh(2022, 3, day, 9),
+                title: "".into(),
+                description: "".into(),
+                state,
+            }],
+            user: "".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 3, 1, 9),
+        };
+
+        let pages = Arc::new(std::sync::Mutex::new(vec![]));
 
-        // Check for folders 'ancient' and 'database'
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+        db.set_dispatch_window(Duration::ZERO);
+        let id_a = db.create_expedient(expedient(1, OrderState::Todo));
+        let id_b = db.create_expedient(expedient(2, OrderState::Todo));
+
+        let hook_pages = pages.clone();
+        db.hook_list_orders(
+            ListOrdersHookOptions {
+                filter: None,
+                sort_by: ListOrdersHookOptionsSortBy::Oldest,
+                max_list_len: 10,
+                from_date: UtcDate::ymdh(2023, 1, 1, 0),
+                show_urgent: true,
+                show_todo: true,
+                show_awaiting: false,
+                show_instore: false,
+                show_done: false,
+                cursor: None,
+                offset: 0,
+                group_by: None,
+                facets: false,
+                order_date: Default::default(),
+                expedient_date: Default::default(),
+                modified_date: Default::default(),
+            },
+            move |page| {
+                hook_pages.lock().unwrap().push(
+                    page.orders
+                        .iter()
+                        .map(|(id, index, _)| (*id, *index))
+                        .collect::<Vec<_>>(),
+                )
+            },
+        );
+        sleep_for(50);
         assert_eq!(
-            2,
-            std::fs::read_dir(&tempdir.path)
-                .unwrap()
-                .into_iter()
-                .count()
+            Some(vec![(id_a, 0), (id_b, 0)]),
+            pages.lock().unwrap().pop()
         );
 
-        // Check that folder 'ancient' only contains 2 item (lock file and folder {year})
+        db.update_expedient(id_a, expedient(3, OrderState::Urgent));
+        sleep_for(50);
         assert_eq!(
-            2,
-            std::fs::read_dir(tempdir.path.join("ancient"))
-                .unwrap()
-                .into_iter()
-                .count()
+            Some(vec![(id_b, 0), (id_a, 0)]),
+            pages.lock().unwrap().pop()
         );
-        // Check that folder 'ancient/{year}' containt only 2 files
+
+        db.update_expedient(id_b, expedient(2, OrderState::Done));
+        let id_c = db.create_expedient(expedient(4, OrderState::Todo));
+        sleep_for(50);
         assert_eq!(
-            2,
-            std::fs::read_dir(tempdir.path.join("ancient").join(&year))
-                .unwrap()
-                .into_iter()
-                .count()
+            Some(vec![(id_a, 0), (id_c, 0)]),
+            pages.lock().unwrap().pop()
         );
 
-        // Check that folder 'dynamic' only contains 2 item (lock file and folder {year})
+        db.delete_expedient(id_a);
+        sleep_for(50);
+        assert_eq!(Some(vec![(id_c, 0)]), pages.lock().unwrap().pop());
+    }
+
+    #[test]
+    fn list_orders_hook_sorts_by_keys() {
+        let tempdir = TempDir::new();
+
+        let expedient = |user: &str, day, state| Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "".into(),
+            orders: vec![Order {
+                date: UtcDate::ymdh(2022, 3, day, 9),
+                title: "".into(),
+                description: "".into(),
+                state,
+            }],
+            user: user.into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 3, 1, 9),
+        };
+
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+        let id_a = db.create_expedient(expedient("Marta", 4, OrderState::Todo));
+        let id_b = db.create_expedient(expedient("anna", 3, OrderState::Urgent));
+        let id_c = db.create_expedient(expedient("Pere", 1, OrderState::Todo));
+        let id_d = db.create_expedient(expedient("Anna", 5, OrderState::Urgent));
+
+        let list_sorted_by = |db: &mut ExpedientDatabase, keys| {
+            let pages = Arc::new(std::sync::Mutex::new(vec![]));
+            let hook_pages = pages.clone();
+            let hook_id = db.hook_list_orders(
+                ListOrdersHookOptions {
+                    filter: None,
+                    sort_by: ListOrdersHookOptionsSortBy::Keys(keys),
+                    max_list_len: 10,
+                    from_date: UtcDate::ymdh(2023, 1, 1, 0),
+                    show_urgent: true,
+                    show_todo: true,
+                    show_awaiting: true,
+                    show_instore: true,
+                    show_done: true,
+                    cursor: None,
+                    offset: 0,
+                    group_by: None,
+                    facets: false,
+                    order_date: Default::default(),
+                    expedient_date: Default::default(),
+                    modified_date: Default::default(),
+                },
+                move |page| {
+                    hook_pages
+                        .lock()
+                        .unwrap()
+                        .push(page.orders.iter().map(|(id, _, _)| *id).collect::<Vec<_>>())
+                },
+            );
+            sleep_for(50);
+            db.release_hook(hook_id);
+            let list = pages.lock().unwrap().pop();
+            list.unwrap()
+        };
+
+        let urgent_first_then_oldest = vec![
+            ListOrdersSortKey {
+                field: ListOrdersSortField::State,
+                descending: false,
+            },
+            ListOrdersSortKey {
+                field: ListOrdersSortField::Date,
+                descending: false,
+            },
+        ];
         assert_eq!(
-            2,
-            std::fs::read_dir(tempdir.path.join("dynamic"))
-                .unwrap()
-                .into_iter()
-                .count()
+            vec![id_b, id_d, id_c, id_a],
+            list_sorted_by(&mut db, urgent_first_then_oldest)
         );
-        // Check that folder 'dynamic/{year}' containt only 2 files
+
+        let by_customer_then_newest = vec![
+            ListOrdersSortKey {
+                field: ListOrdersSortField::Customer,
+                descending: false,
+            },
+            ListOrdersSortKey {
+                field: ListOrdersSortField::Date,
+                descending: true,
+            },
+        ];
         assert_eq!(
-            2,
-            std::fs::read_dir(tempdir.path.join("dynamic").join(&year))
+            vec![id_d, id_b, id_a, id_c],
+            list_sorted_by(&mut db, by_customer_then_newest)
+        );
+    }
+
+    #[test]
+    fn list_orders_hook_sorts_by_time_in_state() {
+        let tempdir = TempDir::new();
+
+        let expedient = |day, state, title: &str| Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "".into(),
+            orders: vec![Order {
+                date: UtcDate::ymdh(2022, 3, day, 9),
+                title: title.into(),
+                description: "".into(),
+                state,
+            }],
+            user: "".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 3, 1, 9),
+        };
+
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+        let id_a = db.create_expedient(expedient(1, OrderState::Todo, ""));
+        let id_b = db.create_expedient(expedient(2, OrderState::Todo, ""));
+        let id_c = db.create_expedient(expedient(3, OrderState::Todo, ""));
+        // Only a change of state restarts the time in state
+        db.update_expedient(id_a, expedient(1, OrderState::Awaiting, ""));
+        db.update_expedient(id_b, expedient(2, OrderState::Todo, "Frenos"));
+
+        let pages = Arc::new(std::sync::Mutex::new(vec![]));
+        let hook_pages = pages.clone();
+        db.hook_list_orders(
+            ListOrdersHookOptions {
+                filter: None,
+                sort_by: ListOrdersHookOptionsSortBy::Keys(vec![ListOrdersSortKey {
+                    field: ListOrdersSortField::TimeInState,
+                    descending: false,
+                }]),
+                max_list_len: 10,
+                from_date: UtcDate::ymdh(2023, 1, 1, 0),
+                show_urgent: true,
+                show_todo: true,
+                show_awaiting: true,
+                show_instore: true,
+                show_done: true,
+                cursor: None,
+                offset: 0,
+                group_by: None,
+                facets: false,
+                order_date: Default::default(),
+                expedient_date: Default::default(),
+                modified_date: Default::default(),
+            },
+            move |page| {
+                hook_pages
+                    .lock()
+                    .unwrap()
+                    .push(page.orders.iter().map(|(id, _, _)| *id).collect::<Vec<_>>())
+            },
+        );
+        sleep_for(50);
+        assert_eq!(Some(vec![id_a, id_c, id_b]), pages.lock().unwrap().pop());
+    }
+
+    #[test]
+    fn list_orders_hook_groups_by_customer() {
+        let tempdir = TempDir::new();
+
+        let order = |day| Order {
+            date: UtcDate::ymdh(2022, 3, day, 9),
+            title: "".into(),
+            description: "".into(),
+            state: OrderState::Todo,
+        };
+        let expedient = |user: &str, orders| Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "".into(),
+            orders,
+            user: user.into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 3, 1, 9),
+        };
+
+        let pages = Arc::new(std::sync::Mutex::new(vec![]));
+
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+        let id_a = db.create_expedient(expedient("Marta", vec![order(2), order(4)]));
+        let id_b = db.create_expedient(expedient("anna", vec![order(3)]));
+        let id_c = db.create_expedient(expedient("Anna ", vec![order(5)]));
+
+        let hook_pages = pages.clone();
+        db.hook_list_orders(
+            ListOrdersHookOptions {
+                filter: None,
+                sort_by: ListOrdersHookOptionsSortBy::Newest,
+                max_list_len: 3,
+                from_date: UtcDate::ymdh(2023, 1, 1, 0),
+                show_urgent: true,
+                show_todo: true,
+                show_awaiting: true,
+                show_instore: true,
+                show_done: true,
+                cursor: None,
+                offset: 0,
+                group_by: Some(ListOrdersSortKey {
+                    field: ListOrdersSortField::Customer,
+                    descending: false,
+                }),
+                facets: false,
+                order_date: Default::default(),
+                expedient_date: Default::default(),
+                modified_date: Default::default(),
+            },
+            move |page| {
+                hook_pages.lock().unwrap().push((
+                    page.orders
+                        .iter()
+                        .map(|(id, index, _)| (*id, *index))
+                        .collect::<Vec<_>>(),
+                    page.groups.clone(),
+                ))
+            },
+        );
+        sleep_for(50);
+
+        let (orders, groups) = pages.lock().unwrap().pop().unwrap();
+        assert_eq!(vec![(id_c, 0), (id_b, 0), (id_a, 1)], orders);
+        assert_eq!(
+            vec![
+                ListOrdersGroup {
+                    header: ListOrdersGroupHeader::Text("Anna".into()),
+                    page_len: 2,
+                    total_len: 2,
+                },
+                ListOrdersGroup {
+                    header: ListOrdersGroupHeader::Text("Marta".into()),
+                    page_len: 1,
+                    total_len: 2,
+                },
+            ],
+            groups
+        );
+    }
+
+    #[test]
+    fn list_orders_hook_counts_facets() {
+        let tempdir = TempDir::new();
+
+        let order = |month, state| Order {
+            date: UtcDate::ymdh(2022, month, 10, 9),
+            title: "".into(),
+            description: "".into(),
+            state,
+        };
+        let expedient = |user: &str, model: &str, orders| Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: model.into(),
+            orders,
+            user: user.into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        };
+
+        let facets = Arc::new(std::sync::Mutex::new(None));
+
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+        db.create_expedient(expedient(
+            "marta",
+            "seat  ibiza",
+            vec![order(2, OrderState::Todo), order(3, OrderState::Done)],
+        ));
+        db.create_expedient(expedient(
+            "Pere",
+            "Seat Ibiza",
+            vec![order(3, OrderState::Urgent)],
+        ));
+        db.create_expedient(expedient(
+            "Marta ",
+            "Audi",
+            vec![order(3, OrderState::Todo)],
+        ));
+
+        let hook_facets = facets.clone();
+        db.hook_list_orders(
+            ListOrdersHookOptions {
+                filter: None,
+                sort_by: ListOrdersHookOptionsSortBy::Newest,
+                max_list_len: 1,
+                from_date: UtcDate::ymdh(2023, 1, 1, 0),
+                show_urgent: true,
+                show_todo: true,
+                show_awaiting: true,
+                show_instore: true,
+                show_done: false,
+                cursor: None,
+                offset: 0,
+                group_by: None,
+                facets: true,
+                order_date: Default::default(),
+                expedient_date: Default::default(),
+                modified_date: Default::default(),
+            },
+            move |page| *hook_facets.lock().unwrap() = page.facets.clone(),
+        );
+        sleep_for(50);
+
+        let facets = facets.lock().unwrap().take().unwrap();
+        assert_eq!(
+            vec![
+                (OrderState::Urgent, 1),
+                (OrderState::Todo, 2),
+                (OrderState::Awaiting, 0),
+                (OrderState::InStore, 0),
+                (OrderState::Done, 0),
+            ],
+            facets.states
+        );
+        assert_eq!(
+            vec![("Seat Ibiza".to_string(), 2), ("Audi".into(), 1)],
+            facets.models
+        );
+        assert_eq!(
+            vec![("Marta".to_string(), 2), ("Pere".into(), 1)],
+            facets.users
+        );
+        assert_eq!(
+            vec![
+                (UtcDate::ymdh(2022, 2, 1, 0), 1),
+                (UtcDate::ymdh(2022, 3, 1, 0), 2),
+            ],
+            facets.months
+        );
+    }
+
+    #[test]
+    fn list_orders_hook_filters_date_ranges() {
+        let tempdir = TempDir::new();
+
+        let order = |day| Order {
+            date: UtcDate::ymdh(2022, 3, day, 9),
+            title: "".into(),
+            description: "".into(),
+            state: OrderState::Todo,
+        };
+        let expedient = |year, orders| Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "".into(),
+            orders,
+            user: "".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(year, 1, 1, 9),
+        };
+        let range = |from: UtcDate, to: UtcDate| DateRange {
+            from: Some(DateBound::Date(from)),
+            to: Some(DateBound::Date(to)),
+        };
+
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+        let id_a = db.create_expedient(expedient(2020, vec![order(1), order(5), order(9)]));
+        let id_b = db.create_expedient(expedient(2022, vec![order(4), order(6)]));
+        sleep_for(5);
+        let modified_since = UtcDate::now();
+        sleep_for(5);
+        db.update_expedient(id_b, expedient(2022, vec![order(4), order(6), order(7)]));
+
+        let list = |db: &mut ExpedientDatabase, order_date, expedient_date, modified_date| {
+            let pages = Arc::new(std::sync::Mutex::new(vec![]));
+            let hook_pages = pages.clone();
+            let hook_id = db.hook_list_orders(
+                ListOrdersHookOptions {
+                    filter: None,
+                    sort_by: ListOrdersHookOptionsSortBy::Oldest,
+                    max_list_len: 10,
+                    from_date: UtcDate::ymdh(2023, 1, 1, 0),
+                    show_urgent: true,
+                    show_todo: true,
+                    show_awaiting: true,
+                    show_instore: true,
+                    show_done: true,
+                    cursor: None,
+                    offset: 0,
+                    group_by: None,
+                    facets: false,
+                    order_date,
+                    expedient_date,
+                    modified_date,
+                },
+                move |page| {
+                    hook_pages.lock().unwrap().push(
+                        page.orders
+                            .iter()
+                            .map(|(id, index, _)| (*id, *index))
+                            .collect::<Vec<_>>(),
+                    )
+                },
+            );
+            sleep_for(50);
+            db.release_hook(hook_id);
+            let list = pages.lock().unwrap().pop();
+            list.unwrap()
+        };
+
+        assert_eq!(
+            vec![(id_b, 0), (id_a, 1), (id_b, 1)],
+            list(
+                &mut db,
+                range(UtcDate::ymdh(2022, 3, 2, 0), UtcDate::ymdh(2022, 3, 6, 23)),
+                DateRange::default(),
+                DateRange::default(),
+            )
+        );
+        assert_eq!(
+            vec![(id_a, 0), (id_a, 1), (id_a, 2)],
+            list(
+                &mut db,
+                DateRange::default(),
+                range(UtcDate::ymdh(2019, 1, 1, 0), UtcDate::ymdh(2021, 1, 1, 0)),
+                DateRange::default(),
+            )
+        );
+        assert_eq!(
+            vec![(id_b, 0), (id_b, 1), (id_b, 2)],
+            list(
+                &mut db,
+                DateRange::default(),
+                DateRange::default(),
+                DateRange {
+                    from: Some(DateBound::Date(modified_since)),
+                    to: None,
+                },
+            )
+        );
+    }
+
+    #[test]
+    fn saved_searches_persist_and_hook_results() {
+        let tempdir = TempDir::new();
+
+        let expedient = |user: &str| Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "".into(),
+            orders: vec![Order {
+                date: UtcDate::ymdh(2022, 3, 1, 9),
+                title: "".into(),
+                description: "".into(),
+                state: OrderState::Todo,
+            }],
+            user: user.into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        };
+        let options = |filter: &str| ListOrdersHookOptions {
+            filter: Some(ListOrdersHookFilter {
+                car_code: "".into(),
+                user: filter.into(),
+                body: "".into(),
+                popularity: 0,
+            }),
+            sort_by: ListOrdersHookOptionsSortBy::Newest,
+            max_list_len: 10,
+            from_date: UtcDate::ymdh(2023, 1, 1, 0),
+            show_urgent: true,
+            show_todo: true,
+            show_awaiting: true,
+            show_instore: true,
+            show_done: true,
+            cursor: None,
+            offset: 3,
+            group_by: None,
+            facets: false,
+            order_date: DateRange::default(),
+            expedient_date: DateRange::default(),
+            modified_date: DateRange::default(),
+        };
+
+        let names = Arc::new(std::sync::Mutex::new(vec![]));
+        {
+            let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+            db.create_expedient(expedient("Pedro"));
+            db.create_expedient(expedient("Pedro"));
+            db.create_expedient(expedient("Eduardo"));
+            let hook_names = names.clone();
+            db.hook_list_saved_searches(move |list| {
+                hook_names
+                    .lock()
+                    .unwrap()
+                    .push(list.iter().map(|name| name.to_string()).collect::<Vec<_>>())
+            });
+            db.save_search("pedro".into(), options("Pedro")).unwrap();
+            db.save_search("eduardo".into(), options("Eduardo"))
+                .unwrap();
+            db.delete_search("eduardo").unwrap();
+            assert!(db.delete_search("unknown").is_err());
+        }
+        assert_eq!(
+            vec![
+                vec![],
+                vec!["pedro".to_string()],
+                vec!["eduardo".to_string(), "pedro".to_string()],
+                vec!["pedro".to_string()],
+            ],
+            *names.lock().unwrap()
+        );
+
+        let mut db = ExpedientDatabase::open(&tempdir.path).unwrap();
+        db.set_dispatch_window(Duration::ZERO);
+        assert_eq!(0, db.read_search("pedro").unwrap().offset);
+        assert!(db.hook_saved_search("eduardo", |_| {}).is_err());
+
+        let counts = Arc::new(std::sync::Mutex::new(vec![]));
+        let hook_counts = counts.clone();
+        let hook_id = db
+            .hook_saved_search("pedro", move |page| {
+                hook_counts.lock().unwrap().push(page.total_len)
+            })
+            .unwrap();
+        let other_calls = Arc::new(std::sync::Mutex::new(0));
+        let hook_other_calls = other_calls.clone();
+        let other_hook_id = db.hook_list_orders(options("Pedro"), move |_| {
+            *hook_other_calls.lock().unwrap() += 1
+        });
+        sleep_for(50);
+        db.create_expedient(expedient("Pedro"));
+        sleep_for(50);
+        let other_calls_before_save = *other_calls.lock().unwrap();
+        // The hook lists the search as it is saved now
+        db.save_search("pedro".into(), options("Eduardo")).unwrap();
+        // Other searches are not listed again
+        db.save_search("eduardo".into(), options("Eduardo"))
+            .unwrap();
+        sleep_for(50);
+        db.release_hook(hook_id);
+        db.release_hook(other_hook_id);
+        assert_eq!(other_calls_before_save, *other_calls.lock().unwrap());
+        let counts = counts.lock().unwrap();
+        assert_eq!(Some(&2), counts.first());
+        assert!(counts.contains(&3));
+        assert_eq!(Some(&1), counts.last());
+    }
+
+    #[test]
+    fn smart_search_hook_ranks_mixed_results() {
+        let tempdir = TempDir::new();
+
+        let expedient = |user: &str, license_plate: &str, title: &str| Expedient {
+            description: "".into(),
+            license_plate: license_plate.into(),
+            model: "".into(),
+            orders: vec![Order {
+                date: UtcDate::ymdh(2022, 3, 1, 9),
+                title: title.into(),
+                description: "".into(),
+                state: OrderState::Todo,
+            }],
+            user: user.into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        };
+
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+        let id_a = db.create_expedient(expedient("Pedro Sala 972123456", "1234 BCD", "Fre"));
+        let id_b = db.create_expedient(expedient(" pedro  sala 972123456", "5678 FGH", "Oli"));
+        let id_c = db.create_expedient(expedient("Eduardo", "1234 BCF", "Pedro"));
+
+        let search = |db: &mut ExpedientDatabase, query: &str| {
+            let pages = Arc::new(std::sync::Mutex::new(vec![]));
+            let hook_pages = pages.clone();
+            let hook_id = db.hook_smart_search(query.into(), 10, move |page| {
+                hook_pages.lock().unwrap().push((
+                    page.kind,
+                    page.results
+                        .iter()
+                        .map(|result| match result.hit {
+                            SmartSearchHit::Expedient { id, .. } => format!("{id:?}"),
+                            SmartSearchHit::Order { id, index, .. } => format!("{id:?} {index}"),
+                            SmartSearchHit::Customer { name, expedients } => {
+                                format!("{name} {expedients}")
+                            }
+                        })
+                        .collect::<Vec<_>>(),
+                ))
+            });
+            sleep_for(50);
+            db.release_hook(hook_id);
+            let page = pages.lock().unwrap().pop();
+            page.unwrap()
+        };
+
+        assert_eq!(
+            (
+                SmartSearchKind::LicensePlate,
+                vec![format!("{id_a:?}"), format!("{id_c:?}")]
+            ),
+            search(&mut db, "1234-bc")
+        );
+        assert_eq!(
+            (
+                SmartSearchKind::Name,
+                vec![
+                    "Pedro Sala 972123456 2".to_string(),
+                    format!("{id_a:?}"),
+                    format!("{id_b:?}"),
+                    format!("{id_c:?} 0"),
+                ]
+            ),
+            search(&mut db, "pedro")
+        );
+        assert_eq!(
+            (
+                SmartSearchKind::Phone,
+                vec![
+                    "Pedro Sala 972123456 2".to_string(),
+                    format!("{id_a:?}"),
+                    format!("{id_b:?}")
+                ]
+            ),
+            search(&mut db, "972 12 34 56")
+        );
+
+        // Results are searched again when the expedients change
+        db.set_dispatch_window(Duration::ZERO);
+        let pages = Arc::new(std::sync::Mutex::new(vec![]));
+        let hook_pages = pages.clone();
+        let hook_id = db.hook_smart_search("1234-bc".into(), 10, move |page| {
+            hook_pages.lock().unwrap().push(page.results.len())
+        });
+        sleep_for(50);
+        db.create_expedient(expedient("Marta", "1234 BCG", ""));
+        sleep_for(50);
+        db.release_hook(hook_id);
+        assert_eq!(Some(&3), pages.lock().unwrap().last());
+    }
+
+    #[test]
+    fn suggestions_hook_ranks_by_use() {
+        let tempdir = TempDir::new();
+
+        let expedient = |model: &str, day, description: &str| Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: model.into(),
+            orders: vec![Order {
+                date: UtcDate::ymdh(2022, 3, day, 9),
+                title: "".into(),
+                description: description.into(),
+                state: OrderState::Todo,
+            }],
+            user: "".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        };
+
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+        db.create_expedient(expedient("Seat Ibiza", 1, "Oli\nFiltre d'oli"));
+        db.create_expedient(expedient("Seat Ibiza", 2, "Filtre d'aire"));
+        db.create_expedient(expedient("Seat Leon", 9, "Filtre d'oli"));
+        db.create_expedient(expedient("Seat Arona", 5, ""));
+
+        let suggestions = |db: &mut ExpedientDatabase, field, filter: &str, max_list_len| {
+            let lists = Arc::new(std::sync::Mutex::new(vec![]));
+            let hook_lists = lists.clone();
+            let hook_id = db.hook_suggestions(
+                SuggestionsHookOptions {
+                    field,
+                    filter: filter.into(),
+                    max_list_len,
+                },
+                move |list| {
+                    hook_lists
+                        .lock()
+                        .unwrap()
+                        .push(list.iter().map(|text| text.to_string()).collect::<Vec<_>>())
+                },
+            );
+            sleep_for(50);
+            db.release_hook(hook_id);
+            let list = lists.lock().unwrap().pop();
+            list.unwrap()
+        };
+
+        assert_eq!(
+            vec!["Seat Ibiza", "Seat Leon"],
+            suggestions(&mut db, SuggestionField::Model, "seat", 2)
+        );
+        assert_eq!(
+            vec!["Filtre d'oli", "Filtre d'aire"],
+            suggestions(&mut db, SuggestionField::OrderDescription, "filtre", 5)
+        );
+    }
+
+    #[test]
+    fn suggest_fields_of_same_vehicle_and_customer() {
+        let tempdir = TempDir::new();
+
+        let expedient = |user: &str, model: &str, license_plate: &str, vin: &str| Expedient {
+            description: "".into(),
+            license_plate: license_plate.into(),
+            model: model.into(),
+            orders: vec![],
+            user: user.into(),
+            vin: vin.into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        };
+
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+        db.create_expedient(expedient("Pepa 923149288", "Seat Ibiza", "1234 BCD", ""));
+        db.create_expedient(expedient("Pepa 923149288", "seat  ibiza", "1234BCD", ""));
+        db.create_expedient(expedient("", "Seat Leon", "", "2HGES16503H591599"));
+        db.create_expedient(expedient("Joan 972000000", "Seat Arona", "5678 FGH", ""));
+
+        let suggestion = |field, value: &str, confidence| FieldSuggestion {
+            field,
+            value: value.into(),
+            confidence,
+        };
+
+        assert_eq!(
+            vec![
+                suggestion(SuggestionField::User, "Pepa 923149288", 1.),
+                suggestion(SuggestionField::Model, "Seat Ibiza", 1.),
+            ],
+            db.suggest_fields(&expedient("", "", "1234-bcd", ""))
+        );
+        assert_eq!(
+            vec![suggestion(SuggestionField::Model, "Seat Leon", 1.)],
+            db.suggest_fields(&expedient("", "", "", "2hges16503h591599"))
+        );
+        assert_eq!(
+            vec![suggestion(SuggestionField::User, "Pepa 923149288", 5. / 6.)],
+            db.suggest_fields(&expedient("pepa", "Seat Ibiza", "", ""))
+                .into_iter()
+                .take(1)
+                .collect::<Vec<_>>()
+        );
+        assert!(db
+            .suggest_fields(&expedient("Pepa 923149288", "Seat Ibiza", "9999 ZZZ", ""))
+            .is_empty());
+    }
+
+    #[test]
+    fn find_and_merge_duplicates() {
+        let tempdir = TempDir::new();
+
+        let expedient = |license_plate: &str, vin: &str, day| Expedient {
+            description: "".into(),
+            license_plate: license_plate.into(),
+            model: "Seat Ibiza".into(),
+            orders: vec![Order {
+                date: UtcDate::ymdh(2022, 3, day, 9),
+                title: format!("Order {day}"),
+                description: "".into(),
+                state: OrderState::Done,
+            }],
+            user: "Pepa 923149288".into(),
+            vin: vin.into(),
+            date: UtcDate::ymdh(2022, 3, day, 9),
+        };
+
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+        db.set_dispatch_window(Duration::ZERO);
+        let id_a = db.create_expedient(expedient("1234 BCD", "", 1));
+        let id_b = db.create_expedient(expedient("1234-bcd", "2HGES16503H591599", 2));
+        db.create_expedient(expedient("1234 BCD", "1RGEF16503R521594", 3));
+        db.create_expedient(expedient("5678 FGH", "", 4));
+
+        let clusters = Arc::new(std::sync::Mutex::new(vec![]));
+        let hook_clusters = clusters.clone();
+        db.hook_duplicates(Default::default(), move |list| {
+            hook_clusters.lock().unwrap().push(
+                list.iter()
+                    .map(|cluster| {
+                        cluster
+                            .expedients
+                            .iter()
+                            .map(|(id, _)| *id)
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        });
+        sleep_for(50);
+        assert_eq!(Some(vec![vec![id_b, id_a]]), clusters.lock().unwrap().pop());
+
+        assert_eq!(id_a, db.merge_expedients(&[id_a, id_b]).unwrap());
+        sleep_for(50);
+        assert_eq!(Some(vec![]), clusters.lock().unwrap().pop());
+
+        let merged = db.read_expedient(id_a).unwrap();
+        assert!(db.read_expedient(id_b).is_none());
+        assert_eq!("2HGES16503H591599", merged.vin);
+        assert_eq!(
+            vec!["Order 1", "Order 2"],
+            merged
+                .orders
+                .iter()
+                .map(|order| order.title.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert!(db.merge_expedients(&[id_a, id_b]).is_err());
+        assert_eq!(Some(id_a), db.merged_into(id_b));
+        assert_eq!(None, db.merged_into(id_a));
+
+        // Undoing the merge forgets it, redoing it records it for the restored expedient
+        let restored = db.undo().unwrap();
+        assert_eq!(None, db.merged_into(id_b));
+        let id_b = *restored.iter().find(|id| **id != id_a).unwrap();
+        db.redo().unwrap();
+        assert_eq!(Some(id_a), db.merged_into(id_b));
+    }
+
+    #[test]
+    fn store_similarity_weights_and_explain_similarity() {
+        let tempdir = TempDir::new();
+
+        let expedient = Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "Seat Ibiza".into(),
+            orders: vec![],
+            user: "Pepa 923149288".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        };
+        let filter = Expedient {
+            model: "Seat Leon".into(),
+            user: "Pepa".into(),
+            ..expedient.clone()
+        };
+        let weights = SimilarityWeights {
+            user: 3.,
+            ..Default::default()
+        };
+
+        let id = {
+            let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+            db.set_similarity_weights(weights).unwrap();
+            db.create_expedient(expedient)
+        };
+
+        let db = ExpedientDatabase::open(&tempdir.path).unwrap();
+        assert_eq!(weights, db.similarity_weights());
+        let breakdown = db.explain_similarity(id, &filter).unwrap();
+        assert_eq!(0.6875, breakdown.similarity);
+        assert_eq!(
+            vec![(SimilarityField::User, 0.75), (SimilarityField::Model, 0.5)],
+            breakdown
+                .fields
+                .iter()
+                .map(|field| (field.field, field.similarity))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reject_invalid_similarity_weights() {
+        let tempdir = TempDir::new();
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+
+        for weights in [
+            SimilarityWeights {
+                user: -1.,
+                ..Default::default()
+            },
+            SimilarityWeights {
+                vin: f32::NAN,
+                ..Default::default()
+            },
+            SimilarityWeights {
+                model: f32::INFINITY,
+                ..Default::default()
+            },
+            SimilarityWeights {
+                description: f32::MAX,
+                ..Default::default()
+            },
+            SimilarityWeights {
+                user: 0.,
+                orders: 0.,
+                description: 0.,
+                model: 0.,
+                license_plate: 0.,
+                vin: 0.,
+            },
+        ] {
+            assert!(matches!(
+                *db.set_similarity_weights(weights).unwrap_err(),
+                ErrorKind::InvalidInput
+            ));
+        }
+        assert_eq!(SimilarityWeights::default(), db.similarity_weights());
+    }
+
+    #[test]
+    fn store_and_search_canonical_license_plates() {
+        let tempdir = TempDir::new();
+
+        let expedient = |license_plate: &str| Expedient {
+            description: "".into(),
+            license_plate: license_plate.into(),
+            model: "".into(),
+            orders: vec![Order {
+                date: UtcDate::ymdh(2022, 3, 1, 9),
+                title: "".into(),
+                description: "".into(),
+                state: OrderState::Todo,
+            }],
+            user: "".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 3, 1, 9),
+        };
+
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+        let id_a = db.create_expedient(expedient("1234-bcd"));
+        let id_b = db.create_expedient(expedient("b1234xy"));
+        let id_c = db.create_expedient(expedient("5keb573"));
+        {
+            let database = db.database.read().unwrap();
+            assert_eq!("1234 BCD", database.read(id_a).unwrap().license_plate);
+            assert_eq!("B 1234 XY", database.read(id_b).unwrap().license_plate);
+            assert_eq!("5keb573", database.read(id_c).unwrap().license_plate);
+        }
+
+        let mut found = vec![];
+        for car_code in ["1234bcd", "12 34", "B-1234", "keb 573"] {
+            let pages = Arc::new(std::sync::Mutex::new(vec![]));
+            let hook_pages = pages.clone();
+            let hook_id = db.hook_list_orders(
+                ListOrdersHookOptions {
+                    filter: Some(ListOrdersHookFilter {
+                        car_code: car_code.into(),
+                        user: "".into(),
+                        body: "".into(),
+                        popularity: 0,
+                    }),
+                    sort_by: ListOrdersHookOptionsSortBy::Oldest,
+                    max_list_len: 10,
+                    from_date: UtcDate::ymdh(2023, 1, 1, 0),
+                    show_urgent: true,
+                    show_todo: true,
+                    show_awaiting: true,
+                    show_instore: true,
+                    show_done: true,
+                    cursor: None,
+                    offset: 0,
+                    group_by: None,
+                    facets: false,
+                    order_date: Default::default(),
+                    expedient_date: Default::default(),
+                    modified_date: Default::default(),
+                },
+                move |page| {
+                    let mut ids: Vec<_> = page.orders.iter().map(|(id, _, _)| *id).collect();
+                    ids.sort_unstable();
+                    hook_pages.lock().unwrap().push(ids)
+                },
+            );
+            sleep_for(50);
+            db.release_hook(hook_id);
+            found.push(pages.lock().unwrap().pop().unwrap());
+        }
+        let mut a_and_b = vec![id_a, id_b];
+        a_and_b.sort_unstable();
+        assert_eq!(vec![vec![id_a], a_and_b, vec![id_b], vec![id_c]], found);
+    }
+
+    #[test]
+    fn validate_expedient_vin() {
+        let tempdir = TempDir::new();
+        let db = ExpedientDatabase::create(&tempdir.path).unwrap();
+
+        let validate = |vin: &str| {
+            db.validate_expedient(
+                None,
+                &Expedient {
+                    description: "".into(),
+                    license_plate: "".into(),
+                    model: "".into(),
+                    orders: vec![],
+                    user: "Pedro".into(),
+                    vin: vin.into(),
+                    date: UtcDate::ymdh(2022, 1, 1, 9),
+                },
+            )
+            .entries
+            .into_iter()
+            .map(|entry| entry.severity)
+            .collect::<Vec<_>>()
+        };
+
+        assert!(validate("").is_empty());
+        assert!(validate("1M8GDM9AXKP042788").is_empty());
+        assert_eq!(
+            vec![ValidationSeverity::Error],
+            validate("1M8GDM9A1KP042788")
+        );
+        assert_eq!(
+            vec![ValidationSeverity::Warning],
+            validate("VSSZZZ6JZ9R000000")
+        );
+        assert_eq!(
+            vec![ValidationSeverity::Error],
+            validate("VSSZZZ6JZ9R00000")
+        );
+    }
+
+    #[test]
+    fn validate_expedient_writes() {
+        let tempdir = TempDir::new();
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+
+        let order = |year, state| Order {
+            date: UtcDate::ymdh(year, 3, 1, 9),
+            title: "".into(),
+            description: "".into(),
+            state,
+        };
+        let expedient = |license_plate: &str, orders| Expedient {
+            description: "".into(),
+            license_plate: license_plate.into(),
+            model: "".into(),
+            orders,
+            user: "".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        };
+        let issues = |write: &ValidatedWrite| {
+            write
+                .report
+                .entries
+                .iter()
+                .map(|entry| entry.issue.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let empty = db.create_validated_expedient(expedient("", vec![]), ValidationMode::Reject);
+        assert_eq!(None, empty.id);
+        assert_eq!(vec![ValidationIssue::EmptyExpedient], issues(&empty));
+        // New expedients start blank
+        let blank = db
+            .create_validated_expedient(expedient("", vec![]), ValidationMode::AcceptWithWarnings)
+            .id
+            .unwrap();
+
+        let active = db
+            .create_validated_expedient(
+                expedient("1234 BCD", vec![order(2022, OrderState::Todo)]),
+                ValidationMode::Reject,
+            )
+            .id
+            .unwrap();
+        let done = db.create_expedient(expedient("1234 BCD", vec![order(2022, OrderState::Done)]));
+
+        let rejected = db.create_validated_expedient(
+            expedient("1234-bcd", vec![order(2021, OrderState::Todo)]),
+            ValidationMode::Reject,
+        );
+        assert_eq!(None, rejected.id);
+        assert_eq!(
+            vec![
+                ValidationIssue::OrderBeforeExpedient(0),
+                ValidationIssue::DuplicateLicensePlate(active),
+            ],
+            issues(&rejected)
+        );
+
+        // Errors are rejected even when accepting warnings
+        let future = db.create_validated_expedient(
+            expedient("", vec![order(3921, OrderState::Todo)]),
+            ValidationMode::AcceptWithWarnings,
+        );
+        assert_eq!(None, future.id);
+        assert_eq!(vec![ValidationIssue::FutureDate(Some(0))], issues(&future));
+
+        let accepted = db
+            .update_validated_expedient(
+                done,
+                Some(0),
+                expedient("5keb573", vec![order(2022, OrderState::Done)]),
+                ValidationMode::AcceptWithWarnings,
+            )
+            .unwrap();
+        assert_eq!(Some(done), accepted.id);
+        assert_eq!(
+            vec![ValidationIssue::UnrecognisedLicensePlate],
+            issues(&accepted)
+        );
+
+        // An expedient is not a duplicate of itself
+        let updated = db
+            .update_validated_expedient(
+                active,
+                Some(0),
+                expedient("1234 BCD", vec![order(2022, OrderState::InStore)]),
+                ValidationMode::Reject,
+            )
+            .unwrap();
+        assert_eq!(Some(active), updated.id);
+
+        let duplicate = db
+            .update_validated_expedient(
+                blank,
+                Some(0),
+                expedient("1234bcd", vec![order(2022, OrderState::Todo)]),
+                ValidationMode::AcceptWithWarnings,
+            )
+            .unwrap();
+        assert_eq!(Some(blank), duplicate.id);
+        assert_eq!(
+            vec![ValidationIssue::DuplicateLicensePlate(active)],
+            issues(&duplicate)
+        );
+
+        // Emptying an expedient is an error
+        let emptied = db
+            .update_validated_expedient(
+                blank,
+                None,
+                expedient("", vec![]),
+                ValidationMode::AcceptWithWarnings,
+            )
+            .unwrap();
+        assert_eq!(None, emptied.id);
+        assert_eq!(3, db.database.read().unwrap().iter_all().count());
+    }
+
+    #[test]
+    fn reject_stale_revisions_and_merge_them() {
+        let tempdir = TempDir::new();
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+
+        let base = Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "Seat Ibiza".into(),
+            orders: vec![],
+            user: "Pedro".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        };
+        let id = db
+            .create_validated_expedient(base.clone(), ValidationMode::Reject)
+            .id
+            .unwrap();
+        assert_eq!(Some(0), db.expedient_revision(id));
+        let revisions = Arc::new(std::sync::Mutex::new(vec![]));
+        let hook_revisions = revisions.clone();
+        db.hook_expedient_revision(id, move |expedient| {
+            hook_revisions
+                .lock()
+                .unwrap()
+                .push(expedient.map(|(_, revision)| revision))
+        });
+
+        let theirs = Expedient {
+            description: "Revisar frenos".into(),
+            ..base.clone()
+        };
+        let write = db
+            .update_validated_expedient(id, Some(0), theirs, ValidationMode::Reject)
+            .unwrap();
+        assert_eq!(Some(1), write.revision);
+
+        let ours = Expedient {
+            model: "Seat Leon".into(),
+            ..base.clone()
+        };
+        assert!(matches!(
+            *db.update_validated_expedient(id, Some(0), ours.clone(), ValidationMode::Reject)
+                .unwrap_err(),
+            ErrorKind::Conflict
+        ));
+
+        let merge = db.merge_expedient_revision(id, &base, &ours).unwrap();
+        assert_eq!(1, merge.revision);
+        assert!(merge.merge.conflicts.is_empty());
+        db.update_validated_expedient(
+            id,
+            Some(merge.revision),
+            merge.merge.expedient,
+            ValidationMode::Reject,
+        )
+        .unwrap();
+
+        let stored = db.read_expedient(id).unwrap();
+        assert_eq!("Seat Leon", stored.model);
+        assert_eq!("Revisar frenos", stored.description);
+        assert_eq!(Some(2), db.expedient_revision(id));
+
+        // Without a revision the write is not checked
+        let write = db
+            .update_validated_expedient(id, None, ours, ValidationMode::Reject)
+            .unwrap();
+        assert_eq!(Some(3), write.revision);
+        assert_eq!(
+            vec![Some(0), Some(1), Some(2), Some(3)],
+            *revisions.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn patch_stored_expedient() {
+        let tempdir = TempDir::new();
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+
+        let order = Order {
+            date: UtcDate::ymdh(2022, 3, 1, 9),
+            title: "Frenos".into(),
+            description: "".into(),
+            state: OrderState::Todo,
+        };
+        let id = db.create_expedient(Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "".into(),
+            orders: vec![],
+            user: "Pedro".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        });
+
+        let patch = |db: &mut ExpedientDatabase, revision, patches| {
+            db.patch_expedient(id, revision, patches, ValidationMode::AcceptWithWarnings)
+        };
+        let write = patch(
+            &mut db,
+            0,
+            vec![
+                ExpedientPatch::SetLicensePlate("1234bcd".into()),
+                ExpedientPatch::InsertOrder { index: 0, order },
+            ],
+        )
+        .unwrap();
+        assert_eq!(Some(1), write.revision);
+        assert!(matches!(
+            *patch(&mut db, 0, vec![ExpedientPatch::RemoveOrder(0)]).unwrap_err(),
+            ErrorKind::Conflict
+        ));
+        assert!(matches!(
+            *patch(&mut db, 1, vec![ExpedientPatch::RemoveOrder(1)]).unwrap_err(),
+            ErrorKind::InvalidInput
+        ));
+
+        let stored = db.read_expedient(id).unwrap();
+        assert_eq!("1234 BCD", stored.license_plate);
+        assert_eq!(1, stored.orders.len());
+        assert_eq!(Some(1), db.expedient_revision(id));
+    }
+
+    #[test]
+    fn undo_and_redo_mutations() {
+        let tempdir = TempDir::new();
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+
+        let expedient = |user: &str| Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "".into(),
+            orders: vec![],
+            user: user.into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        };
+        let user = |db: &ExpedientDatabase, id| db.read_expedient(id).map(|exp| exp.user);
+
+        assert_eq!(None, db.undo());
+        let id_a = db.create_expedient(expedient("Pedro"));
+        let id_b = db.create_expedient(expedient("Eduardo"));
+        db.update_expedient(id_a, expedient("Pedro Sánchez"));
+        db.delete_expedient(id_b);
+
+        let users = Arc::new(std::sync::Mutex::new(vec![]));
+        let hook_users = users.clone();
+        db.hook_expedient(id_a, move |exp| {
+            hook_users
+                .lock()
+                .unwrap()
+                .push(exp.map(|exp| exp.user.clone()))
+        });
+
+        // The deleted expedient is created again with a new Uid
+        let restored = db.undo().unwrap();
+        assert_eq!(1, restored.len());
+        let id_b = restored[0];
+        assert_eq!(Some("Eduardo".into()), user(&db, id_b));
+
+        assert_eq!(Some(vec![id_a]), db.undo());
+        assert_eq!(Some(vec![id_a]), db.redo());
+        assert_eq!(Some(vec![]), db.redo());
+        // Redoing the delete uses the new Uid of the expedient
+        assert_eq!(None, user(&db, id_b));
+        assert_eq!(None, db.redo());
+
+        let id_b = db.undo().unwrap()[0];
+        assert_eq!(Some("Eduardo".into()), user(&db, id_b));
+
+        sleep_for(50);
+        assert_eq!(
+            vec![
+                Some("Pedro Sánchez".to_string()),
+                Some("Pedro".to_string()),
+                Some("Pedro Sánchez".to_string()),
+            ],
+            *users.lock().unwrap()
+        );
+
+        // A new mutation clears the redo stack
+        db.create_expedient(expedient("Ana"));
+        assert_eq!(None, db.redo());
+    }
+
+    #[test]
+    fn bulk_edit_orders_and_query_results() {
+        let tempdir = TempDir::new();
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+
+        let order = |state| Order {
+            date: UtcDate::ymdh(2022, 3, 1, 9),
+            title: "".into(),
+            description: "".into(),
+            state,
+        };
+        let expedient = |model: &str, orders| Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: model.into(),
+            orders,
+            user: "Pedro".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        };
+        let id_a = db.create_expedient(expedient(
+            "seat ibiza",
+            vec![order(OrderState::Todo), order(OrderState::Done)],
+        ));
+        let id_b = db.create_expedient(expedient("Seat Leon", vec![order(OrderState::Todo)]));
+
+        assert_eq!(
+            1,
+            db.bulk_edit(
+                BulkTarget::Orders(vec![(id_a, 0), (id_a, 1)]),
+                BulkOperation::SetModel("Seat Ibiza".into()),
+            )
+            .unwrap()
+        );
+        assert!(db
+            .bulk_edit(
+                BulkTarget::Orders(vec![(id_b, 0), (id_a, 2)]),
+                BulkOperation::AppendNote("Llamar".into()),
+            )
+            .is_err());
+
+        let query = ListOrdersHookOptions {
+            filter: None,
+            sort_by: ListOrdersHookOptionsSortBy::Oldest,
+            max_list_len: 1,
+            from_date: UtcDate::ymdh(2023, 1, 1, 0),
+            show_urgent: false,
+            show_todo: true,
+            show_awaiting: false,
+            show_instore: false,
+            show_done: false,
+            cursor: None,
+            offset: 0,
+            group_by: None,
+            facets: false,
+            order_date: Default::default(),
+            expedient_date: Default::default(),
+            modified_date: Default::default(),
+        };
+        assert_eq!(
+            2,
+            db.bulk_edit(
+                BulkTarget::Query(query),
+                BulkOperation::SetOrderState(OrderState::InStore),
+            )
+            .unwrap()
+        );
+
+        let states = |db: &ExpedientDatabase, id| {
+            db.read_expedient(id)
+                .unwrap()
+                .orders
+                .iter()
+                .map(|order| order.state)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![OrderState::InStore, OrderState::Done],
+            states(&db, id_a)
+        );
+        assert_eq!(vec![OrderState::InStore], states(&db, id_b));
+        assert_eq!("Seat Ibiza", db.read_expedient(id_a).unwrap().model);
+
+        // The whole bulk edit is undone at once
+        let mut undone = db.undo().unwrap();
+        undone.sort_unstable();
+        assert_eq!(vec![id_a, id_b], undone);
+        assert_eq!(vec![OrderState::Todo, OrderState::Done], states(&db, id_a));
+        assert_eq!(vec![OrderState::Todo], states(&db, id_b));
+    }
+
+    #[test]
+    fn commit_transactions_at_once() {
+        let tempdir = TempDir::new();
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+
+        let expedient = |user: &str| Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "".into(),
+            orders: vec![],
+            user: user.into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        };
+        let id_a = db.create_expedient(expedient("Pedro"));
+        let id_b = db.create_expedient(expedient("Eduardo"));
+
+        let users = Arc::new(std::sync::Mutex::new(vec![]));
+        let hook_users = users.clone();
+        db.hook_expedient(id_a, move |exp| {
+            hook_users
+                .lock()
+                .unwrap()
+                .push(exp.map(|exp| exp.user.clone()))
+        });
+
+        // Updating a deleted expedient fails and nothing is written
+        let mut transaction = Transaction::new();
+        transaction
+            .create(expedient("Ana"))
+            .delete(id_b)
+            .update(id_b, expedient("Eduardo Dato"));
+        assert!(db.commit(transaction).is_err());
+        assert_eq!(2, db.count_expedients());
+
+        // A stale revision or a rejected expedient write nothing
+        let mut transaction = Transaction::new();
+        transaction.update_revision(id_a, 1, expedient("Pedro Sánchez"));
+        assert!(matches!(
+            *db.commit(transaction).unwrap_err(),
+            ErrorKind::Conflict
+        ));
+        let mut transaction = Transaction::new();
+        transaction
+            .update(id_a, expedient("Pedro Sánchez"))
+            .create(expedient(""));
+        transaction.mode = ValidationMode::Reject;
+        let rejected = db.commit(transaction).unwrap();
+        assert_eq!(
+            vec![None, None],
+            rejected.iter().map(|write| write.id).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![ValidationIssue::EmptyExpedient],
+            rejected[1]
+                .report
+                .entries
+                .iter()
+                .map(|entry| entry.issue.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(2, db.count_expedients());
+
+        let mut transaction = Transaction::new();
+        transaction
+            .create(expedient("Ana"))
+            .update_revision(id_a, 0, expedient("Pedro Sánchez"))
+            .delete(id_b);
+        let writes = db.commit(transaction).unwrap();
+        assert_eq!(2, writes.len());
+        let created = writes[0].id.unwrap();
+        assert_eq!("Ana", db.read_expedient(created).unwrap().user);
+        assert_eq!((Some(id_a), Some(1)), (writes[1].id, writes[1].revision));
+        assert_eq!(None, db.read_expedient(id_b));
+
+        assert_eq!(
+            vec![Some("Pedro".to_string()), Some("Pedro Sánchez".to_string())],
+            *users.lock().unwrap()
+        );
+
+        db.undo().unwrap();
+        assert_eq!("Pedro", db.read_expedient(id_a).unwrap().user);
+        assert_eq!(2, db.count_expedients());
+    }
+
+    #[test]
+    fn move_order_between_expedients() {
+        let tempdir = TempDir::new();
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+
+        let order = |day, state| Order {
+            date: UtcDate::ymdh(2022, 3, day, 9),
+            title: format!("{}", day),
+            description: "".into(),
+            state,
+        };
+        let expedient = |user: &str, orders| Expedient {
+            description: "".into(),
+            license_plate: "".into(),
+            model: "".into(),
+            orders,
+            user: user.into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        };
+        let id_a = db.create_expedient(expedient(
+            "Pedro",
+            vec![order(1, OrderState::Done), order(2, OrderState::Awaiting)],
+        ));
+        let id_b = db.create_expedient(expedient(
+            "Eduardo",
+            vec![order(1, OrderState::Todo), order(3, OrderState::Todo)],
+        ));
+
+        let calls = Arc::new(std::sync::Mutex::new(vec![]));
+        for id in [id_a, id_b] {
+            let hook_calls = calls.clone();
+            db.hook_expedient(id, move |exp| {
+                hook_calls
+                    .lock()
+                    .unwrap()
+                    .push(exp.map_or(0, |exp| exp.orders.len()))
+            });
+        }
+
+        assert!(matches!(
+            *db.move_order(id_a, 2, id_b).unwrap_err(),
+            ErrorKind::InvalidInput
+        ));
+        assert_eq!(1, db.move_order(id_a, 1, id_b).unwrap());
+
+        assert_eq!(
+            vec![order(1, OrderState::Done)],
+            db.read_expedient(id_a).unwrap().orders
+        );
+        assert_eq!(
+            vec![
+                order(1, OrderState::Todo),
+                order(2, OrderState::Awaiting),
+                order(3, OrderState::Todo)
+            ],
+            db.read_expedient(id_b).unwrap().orders
+        );
+        assert_eq!(vec![2, 2, 1, 3], *calls.lock().unwrap());
+
+        db.undo().unwrap();
+        assert_eq!(2, db.read_expedient(id_a).unwrap().orders.len());
+        assert_eq!(2, db.read_expedient(id_b).unwrap().orders.len());
+
+        // A write rejected by its validation is an error
+        let id_c = db.create_expedient(Expedient {
+            vin: "123".into(),
+            ..expedient("Ana", vec![order(4, OrderState::Todo)])
+        });
+        assert!(matches!(
+            *db.move_order(id_b, 0, id_c).unwrap_err(),
+            ErrorKind::InvalidInput
+        ));
+        assert_eq!(2, db.read_expedient(id_b).unwrap().orders.len());
+    }
+
+    #[test]
+    fn split_expedient_into_two_vehicles() {
+        let tempdir = TempDir::new();
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+
+        let order = |day| Order {
+            date: UtcDate::ymdh(2022, 3, day, 9),
+            title: format!("{}", day),
+            description: "".into(),
+            state: OrderState::Todo,
+        };
+        let id = db.create_expedient(Expedient {
+            description: "Dos coches".into(),
+            license_plate: "1234 BCD".into(),
+            model: "Seat Ibiza".into(),
+            orders: vec![order(1), order(2), order(3)],
+            user: "Pedro".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(2022, 1, 1, 9),
+        });
+
+        let split = |order_indices| ExpedientSplit {
+            order_indices,
+            model: "Seat Leon".into(),
+            license_plate: "5678bcd".into(),
+            vin: "".into(),
+        };
+        assert!(db.split_expedient(id, split(vec![1, 3])).is_err());
+        let new_id = db.split_expedient(id, split(vec![2, 1, 2])).unwrap();
+
+        let expedient = db.read_expedient(id).unwrap();
+        assert_eq!(vec![order(1)], expedient.orders);
+        assert_eq!("Seat Ibiza", expedient.model);
+
+        let new_expedient = db.read_expedient(new_id).unwrap();
+        assert_eq!(vec![order(2), order(3)], new_expedient.orders);
+        assert_eq!("Pedro", new_expedient.user);
+        assert_eq!("5678 BCD", new_expedient.license_plate);
+        assert_eq!(UtcDate::ymdh(2022, 3, 2, 9), new_expedient.date);
+
+        assert_eq!(vec![new_id], db.linked_expedients(id));
+        assert_eq!(vec![id], db.linked_expedients(new_id));
+
+        // Undoing the split deletes the new expedient and its links
+        db.undo().unwrap();
+        assert_eq!(3, db.read_expedient(id).unwrap().orders.len());
+        assert_eq!(None, db.read_expedient(new_id));
+        assert!(db.linked_expedients(id).is_empty());
+
+        // Redoing it links the expedient created again
+        let redone = db.redo().unwrap();
+        assert_eq!(2, redone.len());
+        let new_id = *redone.iter().find(|redone_id| **redone_id != id).unwrap();
+        assert_eq!(
+            vec![order(2), order(3)],
+            db.read_expedient(new_id).unwrap().orders
+        );
+        assert_eq!(vec![new_id], db.linked_expedients(id));
+        assert_eq!(vec![id], db.linked_expedients(new_id));
+    }
+
+    #[test]
+    fn coalesce_burst_of_changes() {
+        let tempdir = TempDir::new();
+
+        let expedient = |description: &str| Expedient {
+            description: description.into(),
+            license_plate: "".into(),
+            model: "".into(),
+            orders: vec![],
+            user: "".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(1921, 3, 8, 21),
+        };
+
+        let calls = Arc::new(std::sync::Mutex::new(vec![]));
+
+        let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+        db.set_dispatch_window(Duration::from_millis(40));
+        let id = db.create_expedient(expedient("Pedro"));
+
+        let hook_calls = calls.clone();
+        db.hook_list_expedients(
+            ListExpedientsHookOptions {
+                filter: expedient("Eduardo"),
+                max_list_len: 10,
+                offset: 0,
+            },
+            move |page| hook_calls.lock().unwrap().push(page.total_len),
+        );
+        sleep_for(20);
+        assert_eq!(vec![0], *calls.lock().unwrap());
+
+        for description in ["Pe", "Pedr", "Eduard", "Eduardo Dato"] {
+            db.update_expedient(id, expedient(description));
+        }
+        sleep_for(20);
+        assert_eq!(vec![0], *calls.lock().unwrap());
+
+        sleep_for(80);
+        assert_eq!(vec![0, 1], *calls.lock().unwrap());
+    }
+
+    #[test]
+    fn expedient_hook_ignores_changes_on_other_expedients() {
+        let tempdir = TempDir::new();
+
+        let expedient = Expedient {
+            description: "Eduardo Dato".into(),
+            license_plate: "".into(),
+            model: "".into(),
+            orders: vec![],
+            user: "".into(),
+            vin: "".into(),
+            date: UtcDate::ymdh(1921, 3, 8, 21),
+        };
+
+        let mut call_count = 0;
+
+        {
+            let mut db = ExpedientDatabase::create(&tempdir.path).unwrap();
+            let id_a = db.create_expedient(expedient.clone());
+            let id_b = db.create_expedient(expedient.clone());
+            db.hook_expedient(id_a, |_| call_count += 1);
+            db.update_expedient(id_b, expedient.clone());
+            db.delete_expedient(id_b);
+        }
+        assert_eq!(1, call_count, "Expected only the initial call");
+    }
+
+    #[test]
+    fn save_do_not_create_files_when_data_is_not_changed() {
+        let tempdir = TempDir::new();
+        {
+            let mut database = ExpedientDatabase::create(&tempdir.path).unwrap();
+            database.save().unwrap();
+            database.create_expedient(Expedient {
+                description: "Pedro".into(),
+                license_plate: "".into(),
+                model: "Car".into(),
+                orders: vec![],
+                user: "".into(),
+                vin: "".into(),
+                date: UtcDate::ymdh(2921, 4, 3, 11),
+            });
+            database.save().unwrap();
+            sleep_for(1100);
+            database.save().unwrap();
+            database.save().unwrap();
+        }
+
+        let year = crate::database::Instant::now().year().to_string();
+
+        // Check for folders 'ancient', 'dynamic', 'last_modified', 'links', 'merged',
+        // 'revisions', 'saved_searches', 'similarity_weights' and 'state_changes'
+        assert_eq!(
+            9,
+            std::fs::read_dir(&tempdir.path)
+                .unwrap()
+                .into_iter()
+                .count()
+        );
+
+        // Check that folder 'ancient' only contains 2 item (lock file and folder {year})
+        assert_eq!(
+            2,
+            std::fs::read_dir(tempdir.path.join("ancient"))
+                .unwrap()
+                .into_iter()
+                .count()
+        );
+        // Check that folder 'ancient/{year}' containt only 2 files
+        assert_eq!(
+            2,
+            std::fs::read_dir(tempdir.path.join("ancient").join(&year))
+                .unwrap()
+                .into_iter()
+                .count()
+        );
+
+        // Check that folder 'dynamic' only contains 2 item (lock file and folder {year})
+        assert_eq!(
+            2,
+            std::fs::read_dir(tempdir.path.join("dynamic"))
+                .unwrap()
+                .into_iter()
+                .count()
+        );
+        // Check that folder 'dynamic/{year}' containt only 2 files
+        assert_eq!(
+            2,
+            std::fs::read_dir(tempdir.path.join("dynamic").join(&year))
+                .unwrap()
+                .into_iter()
+                .count()
+        );
+        // Check that folder 'last_modified/{year}' containt only 2 files
+        assert_eq!(
+            2,
+            std::fs::read_dir(tempdir.path.join("last_modified").join(&year))
                 .unwrap()
                 .into_iter()
                 .count()
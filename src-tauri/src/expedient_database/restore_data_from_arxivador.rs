@@ -1,5 +1,5 @@
 use super::{Expedient, ExpedientDatabase, Order, OrderState, UtcDate};
-use crate::database::Result;
+use crate::{database::Result, license_plate};
 use serde::Deserialize;
 use std::{
     collections::HashMap,
@@ -122,9 +122,11 @@ fn compress_date_hash(mut x: i64) -> String {
 }
 
 fn format_license_plate(license: &String) -> String {
-    let mut formated = license.trim().to_uppercase();
-    insert_space_after_digit(&mut formated);
-    formated
+    license_plate::canonical(license).unwrap_or_else(|| {
+        let mut formated = license.trim().to_uppercase();
+        insert_space_after_digit(&mut formated);
+        formated
+    })
 }
 
 fn format_user(user: &String) -> String {
@@ -134,14 +136,15 @@ fn format_user(user: &String) -> String {
 }
 
 fn insert_space_after_digit(text: &mut String) {
-    if text.len() > 0 {
-        for i in 0..text.len() - 1 {
-            let chars = text.as_bytes();
-            if (chars[i] as char).is_digit(10) && (chars[i + 1] as char).is_alphabetic() {
-                text.insert(4, ' ');
-            }
+    let mut formated = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(char) = chars.next() {
+        formated.push(char);
+        if char.is_ascii_digit() && chars.peek().map_or(false, |next| next.is_alphabetic()) {
+            formated.push(' ');
         }
     }
+    *text = formated;
 }
 
 fn titlecase(text: &String) -> String {
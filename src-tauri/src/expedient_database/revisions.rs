@@ -0,0 +1,33 @@
+use super::*;
+
+/// Changes made on an old revision of an expedient, merged into the stored one
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RevisionMerge {
+    /// Revision of the stored expedient, the merge can be written on it
+    pub revision: u32,
+    pub merge: ThreeWayMerge,
+}
+
+impl<'a> ExpedientDatabase<'a> {
+    /// Amount of times the expedient was updated, `None` if it doesn't exist
+    pub fn expedient_revision(&self, id: Uid) -> Option<u32> {
+        self.database.read().unwrap().revision(id)
+    }
+
+    /// Merges the changes from `base` to `expedient` into the stored expedient
+    pub fn merge_expedient_revision(
+        &self,
+        id: Uid,
+        base: &Expedient,
+        expedient: &Expedient,
+    ) -> Result<RevisionMerge> {
+        let database = self.database.read().unwrap();
+        match (database.read(id), database.revision(id)) {
+            (Some(stored), Some(revision)) => Ok(RevisionMerge {
+                revision,
+                merge: expedient.merge_three_way(base, stored),
+            }),
+            _ => ErrorKind::NotFound.into(),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use super::*;
+use std::collections::BTreeMap;
+
+/// Named presets of `ListOrdersHookOptions`.
+/// They are stored as json, so old presets can be read when new options are added.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SavedSearches {
+    searches: BTreeMap<String, String>,
+}
+
+impl SavedSearches {
+    /// Presets that can't be read anymore are not listed
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.searches
+            .iter()
+            .filter(|(_, options)| serde_json::from_str::<ListOrdersHookOptions>(options).is_ok())
+            .map(|(name, _)| name.as_str())
+    }
+
+    pub fn read(&self, name: &str) -> Option<ListOrdersHookOptions> {
+        serde_json::from_str(self.searches.get(name)?).ok()
+    }
+}
+
+impl<'a> ExpedientDatabase<'a> {
+    /// Saves the options with the name, replacing the search with the same name.
+    /// The cursor and offset are not saved, the search always starts on the first page.
+    pub fn save_search(&mut self, name: String, mut options: ListOrdersHookOptions) -> Result<()> {
+        options.cursor = None;
+        options.offset = 0;
+        {
+            let mut saved_searches = self.saved_searches.write().unwrap();
+            saved_searches.data.searches.insert(
+                name.clone(),
+                serde_json::to_string(&options).expect("Options can be serialized to json"),
+            );
+            saved_searches.store()?;
+        }
+        self.dispatch_saved_searches_change(&name);
+        Ok(())
+    }
+
+    pub fn delete_search(&mut self, name: &str) -> Result<()> {
+        {
+            let mut saved_searches = self.saved_searches.write().unwrap();
+            if saved_searches.data.searches.remove(name).is_none() {
+                return ErrorKind::NotFound.into();
+            }
+            saved_searches.store()?;
+        }
+        self.dispatch_saved_searches_change(name);
+        Ok(())
+    }
+
+    pub fn read_search(&self, name: &str) -> Option<ListOrdersHookOptions> {
+        self.saved_searches.read().unwrap().data.read(name)
+    }
+}
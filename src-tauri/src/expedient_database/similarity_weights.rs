@@ -0,0 +1,38 @@
+use super::*;
+
+impl<'a> ExpedientDatabase<'a> {
+    pub fn similarity_weights(&self) -> SimilarityWeights {
+        self.similarity_weights.read().unwrap().data
+    }
+
+    /// Stores the weights and sorts again the hooks that depend on the similarity
+    pub fn set_similarity_weights(&mut self, weights: SimilarityWeights) -> Result<()> {
+        if !weights.is_valid() {
+            return ErrorKind::InvalidInput.into();
+        }
+        {
+            let mut similarity_weights = self.similarity_weights.write().unwrap();
+            similarity_weights.data = weights;
+            similarity_weights.store()?;
+        }
+        self.interrupt_dispatch();
+        self.dispatch_change(ChangeSet::rescan());
+        Ok(())
+    }
+
+    /// Explains the similarity of an expedient with `expedient`, as it's computed by the hooks
+    pub fn explain_similarity(
+        &self,
+        id: Uid,
+        expedient: &Expedient,
+    ) -> Option<SimilarityBreakdown> {
+        let weights = self.similarity_weights();
+        Some(
+            self.database
+                .read()
+                .unwrap()
+                .read(id)?
+                .similarity_breakdown(expedient, &weights),
+        )
+    }
+}
@@ -0,0 +1,303 @@
+use super::filter::Filter;
+use super::*;
+use std::collections::HashMap;
+
+/// What a smart search query looks like, it decides which fields are searched first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SmartSearchKind {
+    LicensePlate,
+    Vin,
+    Phone,
+    Name,
+    Text,
+}
+
+impl SmartSearchKind {
+    pub fn detect(query: &str) -> Self {
+        let compact = compact(query);
+        let digits = query.chars().filter(|c| c.is_ascii_digit()).count();
+        let letters = compact.chars().filter(|c| c.is_alphabetic()).count();
+
+        if compact.len() == 17
+            && compact.chars().all(|c| c.is_ascii_alphanumeric())
+            && !compact.contains(['I', 'O', 'Q'])
+            && digits > 0
+        {
+            SmartSearchKind::Vin
+        } else if digits >= 6
+            && query
+                .chars()
+                .all(|c| c.is_ascii_digit() || c.is_whitespace() || "+-().".contains(c))
+        {
+            SmartSearchKind::Phone
+        } else if (4..=8).contains(&compact.len())
+            && digits > 0
+            && letters > 0
+            && compact.chars().all(|c| c.is_ascii_alphanumeric())
+            && query.split_whitespace().count() <= 3
+        {
+            SmartSearchKind::LicensePlate
+        } else if letters > 0
+            && query
+                .chars()
+                .all(|c| c.is_alphabetic() || c.is_whitespace())
+        {
+            SmartSearchKind::Name
+        } else {
+            SmartSearchKind::Text
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum SmartSearchHit<'a> {
+    Expedient {
+        id: Uid,
+        expedient: &'a Expedient,
+    },
+    Order {
+        id: Uid,
+        index: usize,
+        expedient: &'a Expedient,
+    },
+    /// Expedients of the same user, counted ignoring case and spaces
+    Customer {
+        name: &'a str,
+        expedients: usize,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SmartSearchResult<'a> {
+    pub hit: SmartSearchHit<'a>,
+    pub score: u32,
+    /// Newest date of the hit, ties on score are sorted by it
+    pub date: UtcDate,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SmartSearchPage<'a> {
+    pub kind: SmartSearchKind,
+    pub results: Vec<SmartSearchResult<'a>>,
+}
+
+/// Uppercase query without spaces or separators, as plates and VINs are compared
+fn compact(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace() && !"-_.".contains(*c))
+        .collect::<String>()
+        .to_uppercase()
+}
+
+fn digits(text: &str) -> String {
+    text.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+fn customer_key(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+pub(super) struct SmartSearch {
+    kind: SmartSearchKind,
+    filter: Filter,
+    compact: String,
+    digits: String,
+}
+
+impl SmartSearch {
+    pub fn new(query: &str) -> Self {
+        Self {
+            kind: SmartSearchKind::detect(query),
+            filter: Filter::new(query),
+            compact: compact(query),
+            digits: digits(query),
+        }
+    }
+
+    pub fn kind(&self) -> SmartSearchKind {
+        self.kind
+    }
+
+    fn code_score(&self, code: &str) -> u32 {
+        let code = compact(code);
+        if self.compact.is_empty() || code.is_empty() {
+            0
+        } else if code == self.compact {
+            100
+        } else if code.starts_with(&self.compact) {
+            60
+        } else if code.contains(&self.compact) {
+            30
+        } else {
+            0
+        }
+    }
+
+    fn phone_score(&self, text: &str) -> u32 {
+        if digits(text).contains(&self.digits) {
+            80
+        } else {
+            0
+        }
+    }
+
+    fn text_score(&self, text: &str, weight: u32) -> u32 {
+        self.filter.test(text) * weight
+    }
+
+    /// Results found on an expedient, not ranked yet
+    pub fn matches<'b>(&self, id: Uid, expedient: &'b Expedient) -> Vec<SmartSearchResult<'b>> {
+        let mut results = vec![];
+        if self.compact.is_empty() {
+            return results;
+        }
+
+        let (user_score, expedient_score) = match self.kind {
+            SmartSearchKind::LicensePlate => (
+                0,
+                self.code_score(&expedient.license_plate)
+                    .max(self.code_score(&expedient.vin) / 2),
+            ),
+            SmartSearchKind::Vin => (
+                0,
+                self.code_score(&expedient.vin)
+                    .max(self.code_score(&expedient.license_plate) / 2),
+            ),
+            SmartSearchKind::Phone => {
+                let user_score = self.phone_score(&expedient.user);
+                (
+                    user_score,
+                    user_score.max(self.phone_score(&expedient.description) / 2),
+                )
+            }
+            SmartSearchKind::Name | SmartSearchKind::Text => {
+                let user_weight = if self.kind == SmartSearchKind::Name {
+                    10
+                } else {
+                    6
+                };
+                let user_score = self.text_score(&expedient.user, user_weight);
+                (
+                    user_score,
+                    user_score
+                        .max(self.text_score(&expedient.model, 4))
+                        .max(self.text_score(&expedient.license_plate, 4))
+                        .max(self.text_score(&expedient.description, 2)),
+                )
+            }
+        };
+
+        if user_score > 0 && !expedient.user.trim().is_empty() {
+            results.push(SmartSearchResult {
+                hit: SmartSearchHit::Customer {
+                    name: expedient.user.trim(),
+                    expedients: 1,
+                },
+                score: user_score + 1,
+                date: expedient.newest_date(),
+            });
+        }
+        if expedient_score > 0 {
+            results.push(SmartSearchResult {
+                hit: SmartSearchHit::Expedient { id, expedient },
+                score: expedient_score,
+                date: expedient.newest_date(),
+            });
+        }
+        if let SmartSearchKind::Name | SmartSearchKind::Text = self.kind {
+            for (index, order) in expedient.orders.iter().enumerate() {
+                let score = self
+                    .text_score(&order.title, 5)
+                    .max(self.text_score(&order.description, 2));
+                if score > 0 {
+                    results.push(SmartSearchResult {
+                        hit: SmartSearchHit::Order {
+                            id,
+                            index,
+                            expedient,
+                        },
+                        score,
+                        date: order.date,
+                    });
+                }
+            }
+        }
+        results
+    }
+
+    /// Joins the customers with the same name, then sorts by score and date
+    pub fn rank<'b>(
+        results: Vec<SmartSearchResult<'b>>,
+        max_list_len: usize,
+    ) -> Vec<SmartSearchResult<'b>> {
+        let mut customers = HashMap::<String, SmartSearchResult<'b>>::new();
+        let mut list = vec![];
+
+        for result in results {
+            match result.hit {
+                SmartSearchHit::Customer { name, expedients } => {
+                    let customer =
+                        customers
+                            .entry(customer_key(name))
+                            .or_insert(SmartSearchResult {
+                                hit: SmartSearchHit::Customer {
+                                    name,
+                                    expedients: 0,
+                                },
+                                score: 0,
+                                date: result.date,
+                            });
+                    if let SmartSearchHit::Customer {
+                        expedients: count, ..
+                    } = &mut customer.hit
+                    {
+                        *count += expedients;
+                    }
+                    customer.score = customer.score.max(result.score);
+                    if result.date.date_hash() > customer.date.date_hash() {
+                        customer.date = result.date;
+                    }
+                }
+                _ => list.push(result),
+            }
+        }
+        list.extend(customers.into_values());
+
+        list.sort_unstable_by_key(|result| (-(result.score as i64), -result.date.date_hash()));
+        list.truncate(max_list_len);
+        list
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detect_query_kind() {
+        assert_eq!(
+            SmartSearchKind::LicensePlate,
+            SmartSearchKind::detect("1234 BCD")
+        );
+        assert_eq!(
+            SmartSearchKind::LicensePlate,
+            SmartSearchKind::detect("B-1234-XY")
+        );
+        assert_eq!(
+            SmartSearchKind::Vin,
+            SmartSearchKind::detect("2HGES16503H591599")
+        );
+        assert_eq!(
+            SmartSearchKind::Phone,
+            SmartSearchKind::detect("+34 972 123 456")
+        );
+        assert_eq!(
+            SmartSearchKind::Name,
+            SmartSearchKind::detect("Eduardo Dato")
+        );
+        assert_eq!(SmartSearchKind::Text, SmartSearchKind::detect("fre 34€"));
+    }
+}
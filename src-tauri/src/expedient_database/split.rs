@@ -0,0 +1,60 @@
+use super::*;
+
+/// Vehicle of the new expedient and the orders moved to it
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExpedientSplit {
+    pub order_indices: Vec<usize>,
+    pub model: String,
+    pub license_plate: String,
+    pub vin: String,
+}
+
+impl<'a> ExpedientDatabase<'a> {
+    /// Moves the orders to a new expedient of the same customer with another vehicle.
+    /// Both expedients are written at once and linked. Returns the id of the new expedient.
+    pub fn split_expedient(&mut self, id: Uid, split: ExpedientSplit) -> Result<Uid> {
+        let mut expedient = match self.read_expedient(id) {
+            Some(expedient) => expedient,
+            None => return ErrorKind::NotFound.into(),
+        };
+        let mut order_indices = split.order_indices;
+        order_indices.sort_unstable();
+        order_indices.dedup();
+        if order_indices
+            .last()
+            .map_or(false, |index| *index >= expedient.orders.len())
+        {
+            return ErrorKind::InvalidInput.into();
+        }
+
+        // Removed from the last one so the indices stay valid
+        let mut orders: Vec<_> = order_indices
+            .iter()
+            .rev()
+            .map(|index| expedient.orders.remove(*index))
+            .collect();
+        orders.reverse();
+        let new_expedient = Expedient {
+            user: expedient.user.clone(),
+            model: split.model,
+            license_plate: split.license_plate,
+            vin: split.vin,
+            description: "".into(),
+            date: orders.first().map_or(expedient.date, |order| order.date),
+            orders,
+        };
+
+        let mut transaction = Transaction::new();
+        transaction
+            .update(id, expedient)
+            .create(new_expedient)
+            .link(id, 1);
+        let writes = self.commit_accepted(transaction)?;
+        Ok(writes[1].id.expect("Accepted writes have an id"))
+    }
+
+    /// Expedients linked to this one, as the ones split from it
+    pub fn linked_expedients(&self, id: Uid) -> Vec<Uid> {
+        self.database.read().unwrap().links(id).to_vec()
+    }
+}
@@ -0,0 +1,207 @@
+use super::*;
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum TransactionOperation {
+    Create(Expedient),
+    /// Fails with `Conflict` if the stored expedient is not on the revision, when it's given
+    Update(Uid, Option<u32>, Expedient),
+    Delete(Uid),
+    /// Deletes the first expedient, recording that it was merged into the second
+    Merge(Uid, Uid),
+    /// Links the expedient with the one created by the previous `Create` operation on this index
+    Link(Uid, usize),
+}
+
+/// Mutations written at once: hooks never see a part of them and they are undone together.
+/// Like any other write, they are only stored on disk with the next `save`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Transaction {
+    pub operations: Vec<TransactionOperation>,
+    /// Applies to every created and updated expedient, if one is rejected nothing is written
+    #[serde(default)]
+    pub mode: ValidationMode,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    pub fn create(&mut self, expedient: Expedient) -> &mut Self {
+        self.operations
+            .push(TransactionOperation::Create(expedient));
+        self
+    }
+    pub fn update(&mut self, id: Uid, expedient: Expedient) -> &mut Self {
+        self.operations
+            .push(TransactionOperation::Update(id, None, expedient));
+        self
+    }
+    pub fn update_revision(&mut self, id: Uid, revision: u32, expedient: Expedient) -> &mut Self {
+        self.operations
+            .push(TransactionOperation::Update(id, Some(revision), expedient));
+        self
+    }
+    pub fn delete(&mut self, id: Uid) -> &mut Self {
+        self.operations.push(TransactionOperation::Delete(id));
+        self
+    }
+    pub fn merge(&mut self, id: Uid, into: Uid) -> &mut Self {
+        self.operations.push(TransactionOperation::Merge(id, into));
+        self
+    }
+    pub fn link(&mut self, id: Uid, create_index: usize) -> &mut Self {
+        self.operations
+            .push(TransactionOperation::Link(id, create_index));
+        self
+    }
+}
+
+impl<'a> ExpedientDatabase<'a> {
+    /// Writes all the operations, or none if one of them refers to an expedient
+    /// that doesn't exist or is rejected on the mode of the transaction.
+    /// Returns the write of every created and updated expedient, in the order of the operations.
+    pub fn commit(&mut self, mut transaction: Transaction) -> Result<Vec<ValidatedWrite>> {
+        {
+            // Expedients must exist, and not be deleted by a previous operation
+            let database = self.database.read().unwrap();
+            let mut deleted = BTreeSet::new();
+            for (index, operation) in transaction.operations.iter().enumerate() {
+                let (id, delete) = match operation {
+                    TransactionOperation::Create(_) => continue,
+                    TransactionOperation::Update(id, revision, _) => {
+                        match (revision, database.revision(*id)) {
+                            (Some(revision), Some(stored)) if *revision != stored => {
+                                return ErrorKind::Conflict.into()
+                            }
+                            _ => (*id, false),
+                        }
+                    }
+                    TransactionOperation::Delete(id) => (*id, true),
+                    TransactionOperation::Merge(id, into) => {
+                        if id == into || database.read(*into).is_none() || deleted.contains(into) {
+                            return ErrorKind::NotFound.into();
+                        }
+                        (*id, true)
+                    }
+                    TransactionOperation::Link(id, create_index) => {
+                        match transaction.operations[..index].get(*create_index) {
+                            Some(TransactionOperation::Create(_)) => (*id, false),
+                            _ => return ErrorKind::InvalidInput.into(),
+                        }
+                    }
+                };
+                if database.read(id).is_none() || deleted.contains(&id) {
+                    return ErrorKind::NotFound.into();
+                }
+                if delete {
+                    deleted.insert(id);
+                }
+            }
+        }
+
+        // Same checks and canonical license plates as the single writes
+        let mut reports = vec![];
+        for operation in &mut transaction.operations {
+            match operation {
+                TransactionOperation::Create(expedient) => {
+                    canonicalize_license_plate(expedient);
+                    reports.push(self.validate_expedient(None, expedient));
+                }
+                TransactionOperation::Update(id, _, expedient) => {
+                    canonicalize_license_plate(expedient);
+                    reports.push(self.validate_expedient(Some(*id), expedient));
+                }
+                _ => {}
+            }
+        }
+        if reports
+            .iter()
+            .any(|report| report.rejects(transaction.mode))
+        {
+            return Ok(reports
+                .into_iter()
+                .map(|report| ValidatedWrite {
+                    id: None,
+                    revision: None,
+                    report,
+                })
+                .collect());
+        }
+
+        self.interrupt_dispatch();
+        let mut changes = ChangeSet::default();
+        let mut mutations = vec![];
+        let mut written = vec![];
+        let mut created = vec![None; transaction.operations.len()];
+        {
+            let mut database = self.database.write().unwrap();
+            for (index, operation) in transaction.operations.into_iter().enumerate() {
+                match operation {
+                    TransactionOperation::Create(expedient) => {
+                        let id = database.push(expedient.clone());
+                        created[index] = Some(id);
+                        changes.created.push(id);
+                        written.push(id);
+                        mutations.push(Mutation::new(id, None, Some(expedient)));
+                    }
+                    TransactionOperation::Update(id, _, expedient) => {
+                        mutations.push(Mutation::new(
+                            id,
+                            database.read(id).cloned(),
+                            Some(expedient.clone()),
+                        ));
+                        database.update(id, expedient);
+                        changes.updated.push(id);
+                        written.push(id);
+                    }
+                    TransactionOperation::Delete(id) => {
+                        mutations.push(Mutation::new(id, database.read(id).cloned(), None));
+                        database.delete(id);
+                        changes.deleted.push(id);
+                    }
+                    TransactionOperation::Merge(id, into) => {
+                        let mut mutation = Mutation::new(id, database.read(id).cloned(), None);
+                        mutation.merged_into = Some(into);
+                        mutations.push(mutation);
+                        database.delete(id);
+                        database.record_merge(id, into);
+                        changes.deleted.push(id);
+                    }
+                    TransactionOperation::Link(id, create_index) => {
+                        if let Some(created_id) = created[create_index] {
+                            database.link(id, created_id);
+                            let mutation = mutations.iter_mut().find(|m| m.id == created_id);
+                            if let Some(mutation) = mutation {
+                                mutation.links.push(id);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let writes = written
+            .into_iter()
+            .zip(reports)
+            .map(|(id, report)| ValidatedWrite {
+                id: Some(id),
+                revision: self.expedient_revision(id),
+                report,
+            })
+            .collect();
+        self.history.record(mutations);
+        self.dispatch_change(changes);
+        Ok(writes)
+    }
+
+    /// Like `commit`, but fails with `InvalidInput` if the transaction is rejected.
+    /// Used to rearrange stored expedients, that may have been stored with errors.
+    pub fn commit_accepted(&mut self, transaction: Transaction) -> Result<Vec<ValidatedWrite>> {
+        let writes = self.commit(transaction)?;
+        if writes.iter().any(|write| write.id.is_none()) {
+            return ErrorKind::InvalidInput.into();
+        }
+        Ok(writes)
+    }
+}
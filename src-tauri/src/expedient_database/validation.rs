@@ -0,0 +1,240 @@
+use super::*;
+use crate::{
+    license_plate::{self, PlateFormat},
+    vin::{self, VinError},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ValidationSeverity {
+    /// The expedient is stored, but the field should be reviewed
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ValidationIssue {
+    /// No customer, vehicle, description nor orders
+    EmptyExpedient,
+    InvalidVin(VinError),
+    /// Not written on any of the formats of `PlateFormat`
+    UnrecognisedLicensePlate,
+    /// Date of the expedient, or of the order with this index, after today
+    FutureDate(Option<usize>),
+    /// Index of an order dated before the expedient
+    OrderBeforeExpedient(usize),
+    /// Another expedient with orders not done has the same license plate
+    DuplicateLicensePlate(Uid),
+}
+
+/// How a write with a non empty report is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ValidationMode {
+    /// Nothing is written if any problem is found
+    Reject,
+    /// Nothing is written if an error is found, warnings are only reported
+    AcceptWithWarnings,
+}
+
+impl Default for ValidationMode {
+    fn default() -> Self {
+        ValidationMode::AcceptWithWarnings
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationEntry {
+    pub severity: ValidationSeverity,
+    pub issue: ValidationIssue,
+}
+
+/// Problems found on the fields of an expedient when it's written
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ValidationReport {
+    pub entries: Vec<ValidationEntry>,
+}
+
+/// Result of a validated write
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidatedWrite {
+    /// Id of the written expedient, `None` if the write was rejected
+    pub id: Option<Uid>,
+    /// Revision of the written expedient
+    pub revision: Option<u32>,
+    pub report: ValidationReport,
+}
+
+impl ValidationReport {
+    pub fn rejects(&self, mode: ValidationMode) -> bool {
+        match mode {
+            ValidationMode::Reject => !self.entries.is_empty(),
+            ValidationMode::AcceptWithWarnings => self
+                .entries
+                .iter()
+                .any(|entry| entry.severity == ValidationSeverity::Error),
+        }
+    }
+
+    fn push(&mut self, severity: ValidationSeverity, issue: ValidationIssue) {
+        self.entries.push(ValidationEntry { severity, issue });
+    }
+
+    fn check_vin(&mut self, vin: &str) {
+        if vin.trim().is_empty() {
+            return;
+        }
+        match vin::validate(vin) {
+            Ok(()) => {}
+            // Outside North America and China the check digit is optional
+            Err(error @ VinError::CheckDigit { .. }) => {
+                let severity = match vin::decode(vin) {
+                    Ok(info) if !info.check_digit_required => ValidationSeverity::Warning,
+                    _ => ValidationSeverity::Error,
+                };
+                self.push(severity, ValidationIssue::InvalidVin(error));
+            }
+            Err(error) => self.push(
+                ValidationSeverity::Error,
+                ValidationIssue::InvalidVin(error),
+            ),
+        }
+    }
+
+    /// New expedients start blank, so it's only an error when an expedient is emptied
+    fn check_empty(&mut self, expedient: &Expedient, is_new: bool) {
+        let is_empty = [
+            &expedient.user,
+            &expedient.model,
+            &expedient.license_plate,
+            &expedient.vin,
+            &expedient.description,
+        ]
+        .iter()
+        .all(|field| field.trim().is_empty());
+        if is_empty && expedient.orders.is_empty() {
+            let severity = if is_new {
+                ValidationSeverity::Warning
+            } else {
+                ValidationSeverity::Error
+            };
+            self.push(severity, ValidationIssue::EmptyExpedient);
+        }
+    }
+
+    fn check_license_plate(&mut self, plate: &str) {
+        if !plate.trim().is_empty() && license_plate::recognize(plate) == PlateFormat::Other {
+            self.push(
+                ValidationSeverity::Warning,
+                ValidationIssue::UnrecognisedLicensePlate,
+            );
+        }
+    }
+
+    fn check_dates(&mut self, expedient: &Expedient) {
+        // A day of margin for the time zones
+        let last_day = UtcDate::now().day_hash() + 1;
+        if expedient.date.day_hash() > last_day {
+            self.push(ValidationSeverity::Error, ValidationIssue::FutureDate(None));
+        }
+        for (index, order) in expedient.orders.iter().enumerate() {
+            if order.date.day_hash() > last_day {
+                self.push(
+                    ValidationSeverity::Error,
+                    ValidationIssue::FutureDate(Some(index)),
+                );
+            }
+            if order.date.day_hash() < expedient.date.day_hash() {
+                self.push(
+                    ValidationSeverity::Warning,
+                    ValidationIssue::OrderBeforeExpedient(index),
+                );
+            }
+        }
+    }
+}
+
+impl<'a> ExpedientDatabase<'a> {
+    /// Checks the fields of `expedient`, that is written on `id` if it already exists
+    pub fn validate_expedient(&self, id: Option<Uid>, expedient: &Expedient) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        report.check_empty(expedient, id.is_none());
+        report.check_vin(&expedient.vin);
+        report.check_license_plate(&expedient.license_plate);
+        report.check_dates(expedient);
+
+        let plate = license_plate::normalize(&expedient.license_plate);
+        if !plate.is_empty() {
+            let database = self.database.read().unwrap();
+            let duplicates = database.iter_all().filter(|(other_id, other)| {
+                Some(*other_id) != id
+                    && other
+                        .orders
+                        .iter()
+                        .any(|order| order.state != OrderState::Done)
+                    && license_plate::normalize(&other.license_plate) == plate
+            });
+            for (other_id, _) in duplicates {
+                report.push(
+                    ValidationSeverity::Warning,
+                    ValidationIssue::DuplicateLicensePlate(other_id),
+                );
+            }
+        }
+        report
+    }
+
+    /// Creates the expedient unless the report rejects it on `mode`
+    pub fn create_validated_expedient(
+        &mut self,
+        expedient: Expedient,
+        mode: ValidationMode,
+    ) -> ValidatedWrite {
+        let report = self.validate_expedient(None, &expedient);
+        if report.rejects(mode) {
+            return ValidatedWrite {
+                id: None,
+                revision: None,
+                report,
+            };
+        }
+        let id = self.create_expedient(expedient);
+        ValidatedWrite {
+            id: Some(id),
+            revision: self.expedient_revision(id),
+            report,
+        }
+    }
+
+    /// Updates the expedient unless the report rejects it on `mode`.
+    /// Fails with `Conflict` if the stored expedient is not on `revision`.
+    /// A `None` revision skips the check, the expedient is overwritten on any revision.
+    pub fn update_validated_expedient(
+        &mut self,
+        id: Uid,
+        revision: Option<u32>,
+        mut expedient: Expedient,
+        mode: ValidationMode,
+    ) -> Result<ValidatedWrite> {
+        match (self.expedient_revision(id), revision) {
+            (None, _) => return ErrorKind::NotFound.into(),
+            (Some(stored), Some(revision)) if stored != revision => {
+                return ErrorKind::Conflict.into()
+            }
+            _ => {}
+        }
+        canonicalize_license_plate(&mut expedient);
+        let report = self.validate_expedient(Some(id), &expedient);
+        if report.rejects(mode) {
+            return Ok(ValidatedWrite {
+                id: None,
+                revision: None,
+                report,
+            });
+        }
+        self.update_expedient(id, expedient);
+        Ok(ValidatedWrite {
+            id: Some(id),
+            revision: self.expedient_revision(id),
+            report,
+        })
+    }
+}
@@ -0,0 +1,173 @@
+//! License plates, recognised by their format so the same plate is always written
+//! and compared the same way
+//!
+//! | Format            | Canonical form |
+//! | ----------------- | -------------- |
+//! | Spanish           | 1234 BCD       |
+//! | SpanishProvincial | B 1234 XY      |
+//! | French            | AB-123-CD      |
+//! | Portuguese        | AA-00-AA       |
+//! | German            | B-AB 1234      |
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PlateFormat {
+    /// Since 2000, 4 digits and 3 consonants
+    Spanish,
+    /// Before 2000, province code, up to 6 digits and up to 2 letters
+    SpanishProvincial,
+    /// Also used in Italy
+    French,
+    Portuguese,
+    /// Only recognised when the parts are separated
+    German,
+    Other,
+}
+
+const SPANISH_LETTERS: &str = "BCDFGHJKLMNPRSTVWXYZ";
+
+const SPANISH_PROVINCES: &[&str] = &[
+    "A", "AB", "AL", "AV", "B", "BA", "BI", "BU", "C", "CA", "CC", "CE", "CO", "CR", "CS", "CU",
+    "GC", "GE", "GI", "GR", "GU", "H", "HU", "IB", "J", "L", "LE", "LO", "LU", "M", "MA", "ML",
+    "MU", "NA", "O", "OR", "OU", "P", "PM", "PO", "S", "SA", "SE", "SG", "SO", "SS", "T", "TE",
+    "TF", "TO", "V", "VA", "VI", "Z", "ZA",
+];
+
+/// Uppercase plate without spaces or separators, as plates are compared
+pub fn normalize(plate: &str) -> String {
+    plate
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// Splits the normalized plate in groups of letters and digits
+fn groups(plate: &str) -> Vec<&str> {
+    let mut groups = vec![];
+    let mut start = 0;
+    for (index, c) in plate.char_indices().skip(1) {
+        let previous = plate[..index].chars().next_back().unwrap();
+        if previous.is_ascii_digit() != c.is_ascii_digit() {
+            groups.push(&plate[start..index]);
+            start = index;
+        }
+    }
+    if start < plate.len() {
+        groups.push(&plate[start..]);
+    }
+    groups
+}
+
+fn is_letters(group: &str, len: std::ops::RangeInclusive<usize>) -> bool {
+    len.contains(&group.len()) && group.chars().all(|c| c.is_ascii_uppercase())
+}
+
+fn is_digits(group: &str, len: std::ops::RangeInclusive<usize>) -> bool {
+    len.contains(&group.len()) && group.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Parts of the plate separated by spaces or dashes
+fn parts(plate: &str) -> Vec<String> {
+    plate
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_uppercase())
+        .collect()
+}
+
+pub fn recognize(plate: &str) -> PlateFormat {
+    let normalized = normalize(plate);
+    let groups = groups(&normalized);
+
+    match groups.as_slice() {
+        [digits, letters]
+            if is_digits(digits, 4..=4)
+                && is_letters(letters, 3..=3)
+                && letters.chars().all(|c| SPANISH_LETTERS.contains(c)) =>
+        {
+            return PlateFormat::Spanish;
+        }
+        [province, digits, letters]
+            if SPANISH_PROVINCES.contains(province)
+                && is_digits(digits, 4..=4)
+                && is_letters(letters, 1..=2) =>
+        {
+            return PlateFormat::SpanishProvincial;
+        }
+        [province, digits] if SPANISH_PROVINCES.contains(province) && is_digits(digits, 1..=6) => {
+            return PlateFormat::SpanishProvincial;
+        }
+        [first, digits, last]
+            if is_letters(first, 2..=2) && is_digits(digits, 3..=3) && is_letters(last, 2..=2) =>
+        {
+            return PlateFormat::French;
+        }
+        _ => {}
+    }
+
+    // Three pairs of letters or digits
+    if normalized.len() == 6
+        && normalized.is_ascii()
+        && groups.len() >= 2
+        && groups.iter().all(|group| group.len() % 2 == 0)
+    {
+        return PlateFormat::Portuguese;
+    }
+
+    if let [area, letters, digits] = parts(plate).as_slice() {
+        if is_letters(area, 1..=3) && is_letters(letters, 1..=2) && is_digits(digits, 1..=4) {
+            return PlateFormat::German;
+        }
+    }
+
+    PlateFormat::Other
+}
+
+/// Plate written on the canonical form of its format, `None` if the format is not recognised
+pub fn canonical(plate: &str) -> Option<String> {
+    let normalized = normalize(plate);
+    let groups = groups(&normalized);
+
+    Some(match recognize(plate) {
+        PlateFormat::Spanish | PlateFormat::SpanishProvincial => groups.join(" "),
+        PlateFormat::French => groups.join("-"),
+        PlateFormat::Portuguese => {
+            [&normalized[0..2], &normalized[2..4], &normalized[4..6]].join("-")
+        }
+        PlateFormat::German => {
+            let parts = parts(plate);
+            format!("{}-{} {}", parts[0], parts[1], parts[2])
+        }
+        PlateFormat::Other => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn recognize_formats() {
+        assert_eq!(PlateFormat::Spanish, recognize("1234bcd"));
+        assert_eq!(PlateFormat::Other, recognize("1234 ABC"));
+        assert_eq!(PlateFormat::SpanishProvincial, recognize("B-1234-XY"));
+        assert_eq!(PlateFormat::SpanishProvincial, recognize("GI 123456"));
+        assert_eq!(PlateFormat::French, recognize("ab 123 cd"));
+        assert_eq!(PlateFormat::Portuguese, recognize("12-AB-34"));
+        assert_eq!(PlateFormat::German, recognize("M-AB 123"));
+        assert_eq!(PlateFormat::Other, recognize("5KEB573"));
+    }
+
+    #[test]
+    fn canonical_form() {
+        assert_eq!(Some("1234 BCD".into()), canonical(" 1234-bcd"));
+        assert_eq!(Some("B 1234 XY".into()), canonical("b1234xy"));
+        assert_eq!(Some("AB-123-CD".into()), canonical("AB 123 CD"));
+        assert_eq!(Some("12-AB-34".into()), canonical("12ab34"));
+        assert_eq!(Some("M-AB 123".into()), canonical("m ab 123"));
+        assert_eq!(None, canonical("5KEB573"));
+        assert_eq!("1234BCD", normalize("1234_bcd "));
+    }
+}
@@ -13,8 +13,10 @@ mod collections;
 mod database;
 mod error;
 mod expedient_database;
+mod license_plate;
 mod mean;
 mod observable;
+mod vin;
 
 use tauri::RunEvent;
 
@@ -34,21 +36,46 @@ fn main() {
             api::hook_expedient,
             api::hook_list_expedients,
             api::hook_list_orders,
-            api::hook_list_users,
-            api::hook_list_models,
-            api::hook_list_license_plates,
-            api::hook_list_order_titles,
-            api::hook_list_vins,
+            api::fetch_orders_page,
+            api::hook_list_saved_searches,
+            api::hook_saved_search,
+            api::hook_smart_search,
+            api::hook_suggestions,
+            api::hook_duplicates,
+            api::set_dispatch_window,
             api::release_hook,
             api::release_all_hooks,
             // expedients
             api::create_expedient,
+            api::validate_expedient,
             api::update_expedient,
+            api::patch_expedient,
             api::delete_expedient,
+            api::merge_expedients,
+            api::merged_expedient,
+            api::bulk_edit,
+            api::commit_transaction,
+            api::move_order,
+            api::split_expedient,
+            api::linked_expedients,
+            api::undo,
+            api::redo,
             api::read_expedient,
+            api::expedient_revision,
+            api::merge_expedient_revision,
+            api::suggest_expedient_fields,
+            api::explain_similarity,
+            api::similarity_weights,
+            api::set_similarity_weights,
             api::count_expedients,
             api::count_orders,
             api::delete_repeated,
+            // saved searches
+            api::save_search,
+            api::delete_search,
+            api::read_search,
+            // vin
+            api::decode_vin,
             // statistics
             api::done_commands_count_vs_days,
             //utils
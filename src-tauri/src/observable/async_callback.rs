@@ -1,25 +1,35 @@
-use super::thread::Thread;
+use super::cancellation_token::CancellationToken;
+use super::worker_pool::{Task, WorkerPool};
 use crate::collections::*;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use std::time::Duration;
 
-pub struct AsyncCallback<'a, Context: 'a + Send + Sync> {
+pub struct AsyncCallback<Context: Send + Sync + 'static> {
     callback: fn(&Context, AsyncCallbackProcess) -> Option<()>,
     context: Arc<Context>,
-    instances: IdMap<AsyncCallbackInstance<'a>>,
+    instances: IdMap<AsyncCallbackInstance>,
 }
 
-pub struct AsyncCallbackInstance<'a> {
-    join_handle: Thread<'a>,
-    request_join: Arc<Mutex<bool>>,
+pub struct AsyncCallbackInstance {
+    task: Task,
+    token: CancellationToken,
+    /// Delayed calls can be skipped if they are cancelled before they start
+    delayed: bool,
 }
 
 pub struct AsyncCallbackProcess {
-    request_join: Arc<Mutex<bool>>,
+    token: CancellationToken,
 }
 
 impl AsyncCallbackProcess {
+    /// Process that is never terminated, to run a scan on the calling thread
+    pub fn uninterrupted() -> Self {
+        Self {
+            token: CancellationToken::default(),
+        }
+    }
     pub fn terminate_if_requested(&self) -> Option<()> {
-        if *self.request_join.lock().unwrap() {
+        if self.token.is_cancelled() {
             None
         } else {
             Some(())
@@ -27,7 +37,7 @@ impl AsyncCallbackProcess {
     }
 }
 
-impl<'a, Context: 'a + Send + Sync> AsyncCallback<'a, Context> {
+impl<Context: Send + Sync + 'static> AsyncCallback<Context> {
     pub fn new(
         context: Context,
         callback: fn(&Context, AsyncCallbackProcess) -> Option<()>,
@@ -38,22 +48,27 @@ impl<'a, Context: 'a + Send + Sync> AsyncCallback<'a, Context> {
             instances: Default::default(),
         }
     }
-    pub fn call(&mut self) {
-        let request_join = Arc::new(Mutex::new(false));
-
-        let requested_join = AsyncCallbackProcess {
-            request_join: request_join.clone(),
+    pub fn context(&self) -> &Context {
+        self.context.as_ref()
+    }
+    /// Runs the callback on the workers once the delay has passed.
+    /// If the call is terminated during the delay, the callback is not executed.
+    pub fn call(&mut self, workers: &WorkerPool, delay: Duration) {
+        let token = CancellationToken::default();
+        let process = AsyncCallbackProcess {
+            token: token.clone(),
         };
         let context = self.context.clone();
-        let cloned_callback = self.callback.clone();
+        let callback = self.callback;
 
-        let join_handle = Thread::spawn(move || {
-            (cloned_callback)(context.as_ref(), requested_join);
+        let task = workers.spawn_after(delay, move || {
+            (callback)(context.as_ref(), process);
         });
 
         self.instances.push(AsyncCallbackInstance {
-            join_handle,
-            request_join,
+            task,
+            token,
+            delayed: !delay.is_zero(),
         });
     }
     pub fn try_terminate_calls(&mut self) -> bool {
@@ -62,26 +77,22 @@ impl<'a, Context: 'a + Send + Sync> AsyncCallback<'a, Context> {
     }
 }
 
-impl<'a> AsyncCallbackInstance<'a> {
-    fn request_join(&mut self) {
-        *self.request_join.as_ref().lock().unwrap() = true;
-    }
+impl AsyncCallbackInstance {
     pub fn try_join(&mut self) -> bool {
-        self.request_join();
-        self.join_handle.try_join()
+        self.token.cancel();
+        (self.delayed && self.task.skip_if_pending()) || self.task.try_join()
     }
 
     pub fn join(&mut self) {
-        self.request_join();
-        self.join_handle.join();
+        if !self.try_join() {
+            self.task.join();
+        }
     }
 }
 
-impl<'a> Drop for AsyncCallbackInstance<'a> {
+impl Drop for AsyncCallbackInstance {
     fn drop(&mut self) {
-        if !self.try_join() {
-            println!("WARN: Blocking thread (with join) because of drop");
-            self.join();
-        }
+        // The callback has been cancelled, so it should end soon
+        self.join();
     }
 }
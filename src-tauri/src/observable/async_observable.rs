@@ -1,27 +1,45 @@
 use super::*;
+use std::sync::Arc;
+use std::time::Duration;
 
-pub struct AsyncObservable<'a, Context: Send + Sync> {
-    hooks: IdMap<AsyncCallback<'a, Context>>,
-    callbacks_to_terminate: IdMap<AsyncCallback<'a, Context>>,
+pub struct AsyncObservable<Context: Send + Sync + 'static> {
+    hooks: IdMap<AsyncCallback<Context>>,
+    callbacks_to_terminate: IdMap<AsyncCallback<Context>>,
+    // Declared last, so the running callbacks are joined before
+    workers: Arc<WorkerPool>,
 }
 
-impl<'a, Context: Send + Sync> Default for AsyncObservable<'a, Context> {
+impl<Context: Send + Sync + 'static> Default for AsyncObservable<Context> {
     fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<Context: Send + Sync + 'static> AsyncObservable<Context> {
+    /// The workers can be shared between observables
+    pub fn new(workers: Arc<WorkerPool>) -> Self {
         Self {
             hooks: Default::default(),
             callbacks_to_terminate: Default::default(),
+            workers,
         }
     }
-}
 
-impl<'a, Context: Send + Sync> AsyncObservable<'a, Context> {
-    pub fn subscrive(&mut self, mut callback: AsyncCallback<'a, Context>, trigger_now: bool) -> Id {
+    pub fn subscrive(&mut self, mut callback: AsyncCallback<Context>, trigger_now: bool) -> Id {
         if trigger_now {
-            callback.call()
+            callback.call(&self.workers, Duration::ZERO)
         }
         self.hooks.push(callback)
     }
 
+    pub fn get(&self, id: Id) -> Option<&AsyncCallback<Context>> {
+        self.hooks.get_ref(id)
+    }
+
+    pub fn contexts(&self) -> impl Iterator<Item = &Context> {
+        self.hooks.iter().map(|callback| callback.data.context())
+    }
+
     pub fn unsubscrive(&mut self, id: Id) {
         if let Some(mut callback) = self.hooks.take(id) {
             if !callback.try_terminate_calls() {
@@ -37,8 +55,25 @@ impl<'a, Context: Send + Sync> AsyncObservable<'a, Context> {
     }
 
     pub fn trigger(&mut self) {
+        self.trigger_after(Duration::ZERO);
+    }
+
+    /// Calls all the callbacks once the delay has passed.
+    /// Triggers terminated before the delay will not execute the callbacks,
+    /// so a burst of `stop_trigger` and `trigger_after` results in only one execution.
+    pub fn trigger_after(&mut self, delay: Duration) {
         for callback_item in self.hooks.iter_mut() {
-            callback_item.data.call()
+            callback_item.data.call(&self.workers, delay)
+        }
+    }
+
+    /// Like `stop_trigger` and `trigger_after`, but only for the callbacks whose context passes the filter
+    pub fn retrigger_filtered_after(&mut self, delay: Duration, filter: impl Fn(&Context) -> bool) {
+        for callback_item in self.hooks.iter_mut() {
+            if filter(callback_item.data.context()) {
+                callback_item.data.try_terminate_calls();
+                callback_item.data.call(&self.workers, delay)
+            }
         }
     }
 
@@ -18,6 +18,9 @@ impl<Context> Callback<Context> {
     pub fn new(context: Context, callback: fn(&Context)) -> Self {
         Self { context, callback }
     }
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
     pub fn call(&self) {
         (self.callback)(&self.context)
     }
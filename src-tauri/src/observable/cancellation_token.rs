@@ -0,0 +1,33 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag to ask a process to stop.
+/// The process has to check it periodically and finish when it's cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cancel_is_shared_between_clones() {
+        let token = CancellationToken::default();
+        let process_token = token.clone();
+        assert!(!process_token.is_cancelled());
+        token.cancel();
+        assert!(process_token.is_cancelled());
+    }
+}
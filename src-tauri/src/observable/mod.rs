@@ -1,11 +1,14 @@
 mod async_callback;
 mod async_observable;
 mod callback;
-mod thread;
+mod cancellation_token;
+mod worker_pool;
 use crate::collections::*;
 pub use async_callback::*;
 pub use async_observable::*;
 pub use callback::*;
+pub use cancellation_token::*;
+pub use worker_pool::*;
 
 pub struct Observable<Context> {
     hooks: IdMap<Callback<Context>>,
@@ -36,6 +39,15 @@ impl<Context> Observable<Context> {
             callback_item.data.call()
         }
     }
+
+    /// Only triggers the callbacks whose context passes the filter
+    pub fn trigger_filtered(&mut self, filter: impl Fn(&Context) -> bool) {
+        for callback_item in self.hooks.iter_mut() {
+            if filter(callback_item.data.context()) {
+                callback_item.data.call()
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -87,6 +99,25 @@ mod test {
         assert_eq!(3, has_been_triggered.load(Ordering::SeqCst));
     }
 
+    #[test]
+    fn trigger_filtered() {
+        let has_been_triggered = Arc::new(AtomicI8::new(0));
+
+        let mut observable = Observable::<(i8, Arc<AtomicI8>)>::default();
+
+        for amount in [2, 3] {
+            observable.subscrive(
+                Callback::new((amount, has_been_triggered.clone()), |(amount, ctx)| {
+                    ctx.fetch_add(*amount, Ordering::SeqCst);
+                }),
+                false,
+            );
+        }
+        observable.trigger_filtered(|(amount, _)| *amount == 3);
+
+        assert_eq!(3, has_been_triggered.load(Ordering::SeqCst));
+    }
+
     #[test]
     fn async_trigger() {
         let has_been_triggered = Arc::new(AtomicI8::new(0));
@@ -0,0 +1,279 @@
+use std::{
+    collections::VecDeque,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Fixed amount of threads that run the jobs in order of arrival.
+/// A job can be delayed, then it will not start before its delay has passed.
+pub struct WorkerPool {
+    queue: Arc<JobQueue>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+struct JobQueue {
+    jobs: Mutex<PendingJobs>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct PendingJobs {
+    jobs: VecDeque<(Instant, Job)>,
+    shutdown: bool,
+}
+
+/// Handle to a job spawned on a `WorkerPool`
+pub struct Task {
+    state: Arc<TaskState>,
+    joined: bool,
+}
+
+struct TaskState {
+    status: Mutex<TaskStatus>,
+    condvar: Condvar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TaskStatus {
+    Pending,
+    Running,
+    Finished { panicked: bool },
+}
+
+impl WorkerPool {
+    pub fn new(size: usize) -> Self {
+        let queue = Arc::new(JobQueue {
+            jobs: Default::default(),
+            condvar: Condvar::new(),
+        });
+
+        let workers = (0..size.max(1))
+            .map(|_| {
+                let queue = queue.clone();
+                std::thread::Builder::new()
+                    .name("observable::WorkerPool".into())
+                    .spawn(move || queue.work())
+                    .expect("failed to spawn thread")
+            })
+            .collect();
+
+        Self { queue, workers }
+    }
+
+    pub fn spawn<F>(&self, f: F) -> Task
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.spawn_after(Duration::ZERO, f)
+    }
+
+    pub fn spawn_after<F>(&self, delay: Duration, f: F) -> Task
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let state = Arc::new(TaskState {
+            status: Mutex::new(TaskStatus::Pending),
+            condvar: Condvar::new(),
+        });
+        let task_state = state.clone();
+
+        let job = Box::new(move || {
+            {
+                let mut status = task_state.status.lock().unwrap();
+                if *status != TaskStatus::Pending {
+                    // Skipped before it started
+                    return;
+                }
+                *status = TaskStatus::Running;
+            }
+            let panicked = catch_unwind(AssertUnwindSafe(f)).is_err();
+            *task_state.status.lock().unwrap() = TaskStatus::Finished { panicked };
+            task_state.condvar.notify_all();
+        });
+
+        self.queue
+            .jobs
+            .lock()
+            .unwrap()
+            .jobs
+            .push_back((Instant::now() + delay, job));
+        self.queue.condvar.notify_all();
+
+        Task {
+            state,
+            joined: false,
+        }
+    }
+}
+
+/// Threads that can work at the same time leaving one core free for the user interface
+pub fn available_workers() -> usize {
+    let cores = std::thread::available_parallelism().map_or(2, |n| n.get());
+    (cores - 1).clamp(1, 4)
+}
+
+impl Default for WorkerPool {
+    fn default() -> Self {
+        Self::new(available_workers())
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        self.queue.jobs.lock().unwrap().shutdown = true;
+        self.queue.condvar.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl JobQueue {
+    fn work(&self) {
+        let mut pending = self.jobs.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            if let Some(index) = pending.jobs.iter().position(|(start, _)| *start <= now) {
+                let (_, job) = pending.jobs.remove(index).unwrap();
+                drop(pending);
+                job();
+                pending = self.jobs.lock().unwrap();
+            } else if let Some(next_start) = pending.jobs.iter().map(|(start, _)| *start).min() {
+                pending = self
+                    .condvar
+                    .wait_timeout(pending, next_start - now)
+                    .unwrap()
+                    .0;
+            } else if pending.shutdown {
+                return;
+            } else {
+                pending = self.condvar.wait(pending).unwrap();
+            }
+        }
+    }
+}
+
+impl Task {
+    /// true -> job is no longer runing
+    /// false -> job is still runing
+    pub fn try_join(&mut self) -> bool {
+        let status = *self.state.status.lock().unwrap();
+        if let TaskStatus::Finished { panicked } = status {
+            self.finish(panicked);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn join(&mut self) {
+        let mut status = self.state.status.lock().unwrap();
+        loop {
+            match *status {
+                TaskStatus::Finished { panicked } => {
+                    drop(status);
+                    self.finish(panicked);
+                    return;
+                }
+                _ => status = self.state.condvar.wait(status).unwrap(),
+            }
+        }
+    }
+
+    /// If the job has not started, it will never run.
+    /// Returns true if the job is no longer runing.
+    pub fn skip_if_pending(&mut self) -> bool {
+        let mut status = self.state.status.lock().unwrap();
+        if *status == TaskStatus::Pending {
+            *status = TaskStatus::Finished { panicked: false };
+            self.state.condvar.notify_all();
+        }
+        drop(status);
+        self.try_join()
+    }
+
+    fn finish(&mut self, panicked: bool) {
+        if panicked && !self.joined {
+            self.joined = true;
+            // Panicking again while unwinding would abort the process
+            if !std::thread::panicking() {
+                panic!("Spawned job paniked");
+            }
+        }
+        self.joined = true;
+    }
+}
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Pool {
+        a: Task,
+        b: Task,
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_two_jobs() {
+        let workers = WorkerPool::new(2);
+        Pool {
+            a: workers.spawn(|| {
+                panic!("Ho no!");
+            }),
+            b: workers.spawn(|| {
+                panic!("Ha ha ha");
+            }),
+        };
+    }
+
+    #[test]
+    fn run_more_jobs_than_workers() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let workers = WorkerPool::new(2);
+        let tasks: Vec<_> = (0..10)
+            .map(|_| {
+                let counter = counter.clone();
+                workers.spawn(move || {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        drop(tasks);
+        assert_eq!(10, counter.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn delayed_job_waits() {
+        let workers = WorkerPool::new(1);
+        let start = Instant::now();
+        let mut task = workers.spawn_after(Duration::from_millis(30), || {});
+        assert!(!task.try_join());
+        task.join();
+        assert!(start.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn skip_delayed_job() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let workers = WorkerPool::new(1);
+        let job_counter = counter.clone();
+        let mut task = workers.spawn_after(Duration::from_millis(30), move || {
+            job_counter.fetch_add(1, Ordering::SeqCst);
+        });
+        assert!(task.skip_if_pending());
+        drop(workers);
+        assert_eq!(0, counter.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,263 @@
+//! Vehicle identification numbers, as defined on ISO 3779
+//!
+//! | Positions | Section                             |
+//! | --------- | ----------------------------------- |
+//! | 1-3       | World manufacturer identifier (WMI) |
+//! | 4-8       | Vehicle attributes                  |
+//! | 9         | Check digit                         |
+//! | 10        | Model year                          |
+//! | 11-17     | Plant and serial number             |
+
+use chrono::{Datelike, Utc};
+use serde::Serialize;
+use std::{collections::HashMap, sync::OnceLock};
+
+/// Same table used by the front-end, manufacturers by WMI of 2 or 3 characters
+const WMI: &str = include_str!("../../../src/database/vin/wmi.txt");
+
+pub const VIN_LEN: usize = 17;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum VinError {
+    Length(usize),
+    /// I, O and Q are not used, they can be confused with 1 and 0
+    InvalidCharacter(char),
+    CheckDigit {
+        expected: char,
+        found: char,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VinInfo {
+    pub manufacturer: Option<&'static str>,
+    pub country: Option<&'static str>,
+    pub model_year: Option<i32>,
+    /// Only mandatory on vehicles made for North America and China
+    pub check_digit_required: bool,
+    pub check_digit_valid: bool,
+}
+
+/// Uppercase VIN without spaces or separators
+pub fn normalize(vin: &str) -> String {
+    vin.chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+fn transliterate(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' => c.to_digit(10),
+        'A' | 'J' => Some(1),
+        'B' | 'K' | 'S' => Some(2),
+        'C' | 'L' | 'T' => Some(3),
+        'D' | 'M' | 'U' => Some(4),
+        'E' | 'N' | 'V' => Some(5),
+        'F' | 'W' => Some(6),
+        'G' | 'P' | 'X' => Some(7),
+        'H' | 'Y' => Some(8),
+        'R' | 'Z' => Some(9),
+        _ => None,
+    }
+}
+
+/// Checks the length and characters of a normalized VIN
+fn check_structure(vin: &str) -> Result<(), VinError> {
+    let len = vin.chars().count();
+    if len != VIN_LEN {
+        return Err(VinError::Length(len));
+    }
+    match vin.chars().find(|c| transliterate(*c).is_none()) {
+        Some(c) => Err(VinError::InvalidCharacter(c)),
+        None => Ok(()),
+    }
+}
+
+/// Check digit of the position 9, computed from the other 16 characters
+pub fn check_digit(vin: &str) -> Result<char, VinError> {
+    let vin = normalize(vin);
+    check_structure(&vin)?;
+
+    const WEIGHTS: [u32; VIN_LEN] = [8, 7, 6, 5, 4, 3, 2, 10, 0, 9, 8, 7, 6, 5, 4, 3, 2];
+    let sum: u32 = vin
+        .chars()
+        .zip(WEIGHTS)
+        .filter_map(|(c, weight)| Some(transliterate(c)? * weight))
+        .sum();
+
+    Ok(match sum % 11 {
+        10 => 'X',
+        remainder => std::char::from_digit(remainder, 10).unwrap(),
+    })
+}
+
+/// Checks the structure and the check digit
+pub fn validate(vin: &str) -> Result<(), VinError> {
+    let expected = check_digit(vin)?;
+    let found = normalize(vin).chars().nth(8).unwrap();
+    if expected == found {
+        Ok(())
+    } else {
+        Err(VinError::CheckDigit { expected, found })
+    }
+}
+
+fn check_digit_required(vin: &str) -> bool {
+    matches!(vin.chars().next(), Some('1'..='5' | 'L'))
+}
+
+fn wmi_table() -> &'static HashMap<&'static str, &'static str> {
+    static TABLE: OnceLock<HashMap<&'static str, &'static str>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        WMI.lines()
+            .filter_map(|line| line.trim_end_matches('\r').split_once('\t'))
+            .collect()
+    })
+}
+
+pub fn manufacturer(vin: &str) -> Option<&'static str> {
+    let vin = normalize(vin);
+    let table = wmi_table();
+    table
+        .get(vin.get(0..3)?)
+        .or_else(|| table.get(vin.get(0..2)?))
+        .copied()
+}
+
+/// Country assigned to the first two characters of the WMI, on ISO 3780
+pub fn country(vin: &str) -> Option<&'static str> {
+    const COUNTRIES: [(char, char, char, &str); 38] = [
+        ('A', 'A', 'H', "South Africa"),
+        ('J', 'A', '0', "Japan"),
+        ('K', 'L', 'R', "South Korea"),
+        ('L', 'A', '0', "China"),
+        ('M', 'A', 'E', "India"),
+        ('M', 'F', 'K', "Indonesia"),
+        ('M', 'L', 'R', "Thailand"),
+        ('P', 'L', 'R', "Malaysia"),
+        ('R', 'F', 'K', "Taiwan"),
+        ('S', 'A', 'M', "United Kingdom"),
+        ('S', 'N', 'T', "Germany"),
+        ('S', 'U', 'Z', "Poland"),
+        ('T', 'A', 'H', "Switzerland"),
+        ('T', 'J', 'P', "Czech Republic"),
+        ('T', 'R', 'V', "Hungary"),
+        ('T', 'W', '1', "Portugal"),
+        ('U', 'H', 'M', "Denmark"),
+        ('U', 'N', 'T', "Ireland"),
+        ('U', 'U', 'Z', "Romania"),
+        ('U', '5', '7', "Slovakia"),
+        ('V', 'A', 'E', "Austria"),
+        ('V', 'F', 'R', "France"),
+        ('V', 'S', 'W', "Spain"),
+        ('V', 'X', '2', "Serbia"),
+        ('V', '3', '5', "Croatia"),
+        ('V', '6', '0', "Estonia"),
+        ('W', 'A', '0', "Germany"),
+        ('X', 'A', 'E', "Bulgaria"),
+        ('X', 'L', 'R', "Netherlands"),
+        ('X', 'S', 'W', "Russia"),
+        ('X', 'X', '2', "Luxembourg"),
+        ('X', '3', '0', "Russia"),
+        ('Y', 'A', 'E', "Belgium"),
+        ('Y', 'F', 'K', "Finland"),
+        ('Y', 'S', 'W', "Sweden"),
+        ('Z', 'A', 'R', "Italy"),
+        ('1', 'A', '0', "United States"),
+        ('2', 'A', '0', "Canada"),
+    ];
+    // Second characters are ordered A-Z and then 1-9, 0
+    let order = |c: char| match c {
+        'A'..='Z' => c as u32 - 'A' as u32,
+        '1'..='9' => 26 + c as u32 - '1' as u32,
+        '0' => 35,
+        _ => u32::MAX,
+    };
+
+    let vin = normalize(vin);
+    let mut chars = vin.chars();
+    let (first, second) = (chars.next()?, chars.next()?);
+    match first {
+        '3' => return Some("Mexico"),
+        '4' | '5' => return Some("United States"),
+        '9' => return Some("Brazil"),
+        _ => {}
+    }
+    COUNTRIES
+        .iter()
+        .find(|(region, from, to, _)| {
+            *region == first && (order(*from)..=order(*to)).contains(&order(second))
+        })
+        .map(|(_, _, _, country)| *country)
+}
+
+/// Model year of the position 10. Codes repeat every 30 years, so the newest year
+/// not after `current_year + 1` is chosen, unless the position 7 tells the cycle
+/// as on North American vehicles (a letter since 2010).
+pub fn model_year(vin: &str, current_year: i32) -> Option<i32> {
+    const CODES: &str = "ABCDEFGHJKLMNPRSTVWXY123456789";
+
+    let vin = normalize(vin);
+    check_structure(&vin).ok()?;
+    let code = vin.chars().nth(9)?;
+    let year = 1980 + CODES.find(code)? as i32;
+
+    if vin.starts_with(['1', '2', '3', '4', '5']) {
+        let newer_cycle = vin.chars().nth(6)?.is_alphabetic();
+        return Some(if newer_cycle { year + 30 } else { year });
+    }
+
+    let cycles = (current_year + 1 - year).max(0) / 30;
+    Some(year + cycles * 30)
+}
+
+pub fn decode(vin: &str) -> Result<VinInfo, VinError> {
+    let normalized = normalize(vin);
+    check_structure(&normalized)?;
+
+    Ok(VinInfo {
+        manufacturer: manufacturer(&normalized),
+        country: country(&normalized),
+        model_year: model_year(&normalized, Utc::now().year()),
+        check_digit_required: check_digit_required(&normalized),
+        check_digit_valid: validate(&normalized).is_ok(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn validate_check_digit() {
+        assert_eq!(Ok(()), validate("1M8GDM9AXKP042788"));
+        assert_eq!(Ok(()), validate("1m8gdm9a-xkp042788"));
+        assert_eq!(
+            Err(VinError::CheckDigit {
+                expected: 'X',
+                found: '1'
+            }),
+            validate("1M8GDM9A1KP042788")
+        );
+        assert_eq!(Err(VinError::Length(16)), validate("1M8GDM9AXKP04278"));
+        assert_eq!(
+            Err(VinError::InvalidCharacter('O')),
+            validate("1M8GDM9AXKP0427O8")
+        );
+    }
+
+    #[test]
+    fn decode_vin() {
+        assert_eq!(Some("Honda America"), manufacturer("1HGES16503H591599"));
+        assert_eq!(Some("United States"), country("1HGES16503H591599"));
+        assert_eq!(Some("Spain"), country("VSSZZZ6JZ9R000000"));
+        assert_eq!(Some("Germany"), country("WVWZZZ1JZ3W386752"));
+
+        // North American: position 7 is a digit before 2010
+        assert_eq!(Some(2003), model_year("1HGES16503H591599", 2022));
+        assert_eq!(Some(2009), model_year("VSSZZZ6JZ9R000000", 2022));
+        assert_eq!(Some(2021), model_year("VSSZZZ6JZMR000000", 2022));
+        assert_eq!(Some(1991), model_year("VSSZZZ6JZMR000000", 2019));
+    }
+}
@@ -24,6 +24,11 @@ function createCallback(callback: (args: any[]) => void) {
 export type ListExpedientsHookOptions = {
 	filter: Expedient,
 	max_list_len: number,
+	offset?: number,
+}
+export type ListExpedientsPage = {
+	expedients: [ExpedientId, Expedient, number][],
+	total_len: number,
 }
 export type ListOrdersHookOptionsSortBy = {
 	sort_by: "Oldest" | "Newest",
@@ -34,20 +39,49 @@ export type ListOrdersHookOptionsSortBy = {
 	show_awaiting: boolean,
 	show_instore: boolean,
 	show_done: boolean,
+	cursor?: ListOrdersCursor,
+	offset?: number,
+}
+export type ListOrdersCursor = {
+	sort_values: ({ Number: number } | { Text: string })[],
+	expedient_id: ExpedientId,
+	order_index: number,
+}
+export type ListOrdersPage = {
+	orders: [ExpedientId, number, Expedient][],
+	groups: { header: { Text: string } | { State: string } | { Date: UtcDate }, page_len: number, total_len: number }[],
+	total_len: number,
+	next_cursor: ListOrdersCursor | null,
+	facets: null | {
+		states: [string, number][],
+		models: [string, number][],
+		users: [string, number][],
+		months: [UtcDate, number][],
+	},
 }
 
-export function createHook(hook_name: "expedient", id: ExpedientId): [Accessor<Expedient | null>];
-export function createHook(
-	hook_name: "list_users" | "list_models" | "list_license_plates" | "list_vins" | "list_order_titles",
+export type SuggestionField = "User" | "Model" | "LicensePlate" | "Vin" | "OrderTitle" | "OrderDescription"
+export type SuggestionsHookOptions = {
+	field: SuggestionField,
 	filter: string,
+	max_list_len?: number,
+}
+
+export function createHook(hook_name: "expedient", id: ExpedientId): [Accessor<[Expedient, number] | null>];
+export function createHook(
+	hook_name: "suggestions",
+	options: SuggestionsHookOptions,
 	deferOptions?: { defer: true }
-): [Accessor<string[] | null>, Setter<string>];
+): [Accessor<string[] | null>, Setter<SuggestionsHookOptions>];
 export function createHook(
 	hook_name: "list_expedients",
 	options: ListExpedientsHookOptions,
 	deferOptions?: { defer: true }
-);
-export function createHook(hook_name: "list_orders", options: ListOrdersHookOptionsSortBy);
+): [Accessor<ListExpedientsPage | null>, Setter<ListExpedientsHookOptions>];
+export function createHook(
+	hook_name: "list_orders",
+	options: ListOrdersHookOptionsSortBy
+): [Accessor<ListOrdersPage | null>, Setter<ListOrdersHookOptionsSortBy>];
 export function createHook(hook_name: string, options: {}, deferOptions?: { defer: true }) {
 	const [hookData, setHookData] = createSignal(null)
 	const [hookOptions, setHookOptions] = createSignal(options)
@@ -59,7 +93,6 @@ export function createHook(hook_name: string, options: {}, deferOptions?: { defe
 	createEffect(on(hookOptions, async () => {
 		let params
 		if (hook_name == "expedient") params = { jsCallback, expedientId: hookOptions() }
-		else if (typeof hookOptions() == "string") params = { jsCallback, filter: hookOptions() }
 		else params = { jsCallback, options: hookOptions() }
 
 		const newHookId = await invoke("hook_" + hook_name, params)
@@ -85,4 +118,12 @@ export function createHook(hook_name: string, options: {}, deferOptions?: { defe
 async function releaseHook({ jsCallback, hookId }) {
 	await invoke("release_hook", { hookId })
 	window.callbacks.delete(jsCallback)
+}
+
+export function createSuggestionsHook(
+	field: SuggestionField,
+	deferOptions?: { defer: true }
+): [Accessor<string[] | null>, (filter: string) => void] {
+	const [suggestions, setOptions] = createHook("suggestions", { field, filter: "" }, deferOptions)
+	return [suggestions, (filter: string) => setOptions({ field, filter })]
 }
\ No newline at end of file
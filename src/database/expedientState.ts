@@ -1,12 +1,22 @@
 import { invoke } from "@tauri-apps/api/tauri"
-import { Expedient, ExpedientId } from "./types"
+import { Expedient, ExpedientId, ExpedientPatch, ValidatedWrite, ValidationReport } from "./types"
 
 export async function createExpedient(expedient: Expedient) {
 	return await invoke("create_expedient", { expedient }) as ExpedientId
 }
 
-export async function updateExpedient(id: ExpedientId, expedient: Expedient) {
-	return await invoke("update_expedient", { id, expedient })
+export async function validateExpedient(id: ExpedientId | null, expedient: Expedient) {
+	return await invoke("validate_expedient", { id, expedient }) as ValidationReport | null
+}
+
+// Without a revision the expedient is overwritten, even if it was changed after being read
+export async function updateExpedient(id: ExpedientId, expedient: Expedient, revision?: number) {
+	return await invoke("update_expedient", { id, expedient, revision }) as ValidatedWrite
+}
+
+// Fails if the expedient is no longer on `revision`
+export async function patchExpedient(id: ExpedientId, revision: number, patches: ExpedientPatch[]) {
+	return await invoke("patch_expedient", { id, revision, patches }) as ValidatedWrite
 }
 
 export async function deleteExpedient(id: ExpedientId) {
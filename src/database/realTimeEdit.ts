@@ -1,14 +1,27 @@
 import { Accessor, createEffect, createSignal, on, Setter } from "solid-js";
 import { createHook } from "./expedientHook";
-import { updateExpedient } from "./expedientState";
-import { Expedient, ExpedientId } from "./types";
+import { patchExpedient } from "./expedientState";
+import { Expedient, ExpedientId, expedientPatches } from "./types";
 
 export function realTimeDatabaseExpedientEditor(
 	expedientId: ExpedientId,
 ): [Accessor<Expedient | null>, Setter<Expedient>] {
 
 	const [editorValue, updateEditor] = createSignal<Expedient | null>(null)
-	const [expedient] = createHook("expedient", expedientId)
+	const [hookedExpedient] = createHook("expedient", expedientId)
+	const expedient = () => hookedExpedient()?.[0] ?? null
+	// Patches are made from the expedient on this revision, and rejected if it was changed after it
+	let base: Expedient | null = null
+	let revision: number | null = null
+	createEffect(on(hookedExpedient, () => {
+		const hooked = hookedExpedient()
+		if (!hooked) {
+			base = null
+			revision = null
+		} else if (revision == null || hooked[1] >= revision) {
+			[base, revision] = hooked
+		}
+	}))
 
 	createEffect(on(expedient, () => {
 		if (!expedient()) {
@@ -19,7 +32,19 @@ export function realTimeDatabaseExpedientEditor(
 
 	realTimeDatabaseEditor<Expedient>(
 		expedient,
-		(expedient) => updateExpedient(expedientId, expedient),
+		async (expedient) => {
+			if (!base || revision == null) return false
+			const patches = expedientPatches(base, expedient)
+			if (!patches.length) return false
+			const write = await patchExpedient(expedientId, revision, patches).catch(() => null)
+			if (write?.id == null || write.revision == null) return false
+			// The hook may have already received this or a later revision
+			if (write.revision > revision) {
+				base = expedient
+				revision = write.revision
+			}
+			return true
+		},
 		editorValue,
 		updateEditor,
 	)
@@ -31,7 +56,7 @@ let id = 0
 
 export function realTimeDatabaseEditor<T>(
 	databaseHookReceiver: Accessor<null | T>,
-	sendUpdateToDatabase: (newValue: T) => void,
+	sendUpdateToDatabase: (newValue: T) => Promise<boolean>,
 	editorValue: Accessor<null | T>,
 	updateEditor: (newValue: T) => void) {
 
@@ -42,7 +67,10 @@ export function realTimeDatabaseEditor<T>(
 		const value = editorValue()
 		if (!value) return
 		sendedValue = JSON.stringify(value)
-		sendUpdateToDatabase(value)
+		sendUpdateToDatabase(value).then(written => {
+			// A rejected value is never received back
+			if (!written) sendedValue = null
+		})
 	}
 
 	createEffect(on(databaseHookReceiver, () => {
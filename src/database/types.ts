@@ -15,6 +15,21 @@ export type Expedient = {
 
 export type User = string
 
+export type ValidationEntry = {
+	severity: "Warning" | "Error",
+	issue: string | { [issue: string]: unknown },
+}
+
+export type ValidationReport = {
+	entries: ValidationEntry[],
+}
+
+export type ValidatedWrite = {
+	id: ExpedientId | null,
+	revision: number | null,
+	report: ValidationReport,
+}
+
 export type Order = {
 	title: string,
 	description: string,
@@ -24,6 +39,47 @@ export type Order = {
 
 export type OrderState = "Urgent" | "Todo" | "Awaiting" | "InStore" | "Done"
 
+export type ExpedientPatch =
+	{ SetUser: string } |
+	{ SetModel: string } |
+	{ SetLicensePlate: string } |
+	{ SetVin: string } |
+	{ SetDescription: string } |
+	{ SetDate: UtcDate } |
+	{ InsertOrder: { index: number, order: Order } } |
+	{ RemoveOrder: number } |
+	{ UpdateOrder: { index: number, order: Order } }
+
+// Patches that turn `from` into `to`, only the changed orders are sent
+export function expedientPatches(from: Expedient, to: Expedient): ExpedientPatch[] {
+	const patches: ExpedientPatch[] = []
+	if (from.user != to.user) patches.push({ SetUser: to.user })
+	if (from.model != to.model) patches.push({ SetModel: to.model })
+	if (from.license_plate != to.license_plate) patches.push({ SetLicensePlate: to.license_plate })
+	if (from.vin != to.vin) patches.push({ SetVin: to.vin })
+	if (from.description != to.description) patches.push({ SetDescription: to.description })
+	if (compareUtcDate(from.date, to.date) != 0) patches.push({ SetDate: to.date })
+
+	const sameOrder = (a: Order, b: Order) => JSON.stringify(a) == JSON.stringify(b)
+	let prefix = 0
+	while (prefix < from.orders.length && prefix < to.orders.length
+		&& sameOrder(from.orders[prefix], to.orders[prefix])) prefix++
+	let suffix = 0
+	while (suffix < from.orders.length - prefix && suffix < to.orders.length - prefix
+		&& sameOrder(from.orders[from.orders.length - 1 - suffix], to.orders[to.orders.length - 1 - suffix])) suffix++
+
+	const removed = from.orders.length - prefix - suffix
+	const inserted = to.orders.length - prefix - suffix
+	for (let index = prefix; index < prefix + Math.min(removed, inserted); index++) {
+		if (!sameOrder(from.orders[index], to.orders[index]))
+			patches.push({ UpdateOrder: { index, order: to.orders[index] } })
+	}
+	for (let i = inserted; i < removed; i++) patches.push({ RemoveOrder: prefix + inserted })
+	for (let index = prefix + removed; index < prefix + inserted; index++)
+		patches.push({ InsertOrder: { index, order: to.orders[index] } })
+	return patches
+}
+
 export function newBlankExpedient(): Expedient {
 	return {
 		date: utcDateNow(),
@@ -2,7 +2,7 @@ import { createEffect, createSignal, For, on, Show, untrack } from 'solid-js'
 import Button from '../../atoms/Button'
 import InputText from '../../atoms/InputText'
 import InputTextArea from '../../atoms/InputTextArea'
-import { createHook } from '../../database/expedientHook'
+import { createSuggestionsHook } from '../../database/expedientHook'
 import { deleteExpedient } from '../../database/expedientState'
 import { realTimeDatabaseExpedientEditor } from '../../database/realTimeEdit'
 import { Expedient, ExpedientId, expedientIsBlank, newBlankOrder, Order, sortOrdersByPriority, userFirstName } from '../../database/types'
@@ -102,16 +102,16 @@ export default function ExpedientEditor({ expedientId }: Props) {
 		}
 	})
 
-	const [userSuggestions, setUserFilter] = createHook("list_users", "", { defer: true })
+	const [userSuggestions, setUserFilter] = createSuggestionsHook("User", { defer: true })
 	createEffect(() => setUserFilter(expedient()?.user ?? ""))
 
-	const [modelSuggestions, setModelFilter] = createHook("list_models", "", { defer: true })
+	const [modelSuggestions, setModelFilter] = createSuggestionsHook("Model", { defer: true })
 	createEffect(() => setModelFilter(expedient()?.model ?? ""))
 
-	const [licenseSuggestions, setLicenseFilter] = createHook("list_license_plates", "", { defer: true })
+	const [licenseSuggestions, setLicenseFilter] = createSuggestionsHook("LicensePlate", { defer: true })
 	createEffect(() => setLicenseFilter(expedient()?.license_plate.replaceAll(" ", "_") ?? ""))
 
-	const [vinSuggestions, setvinFilter] = createHook("list_vins", "", { defer: true })
+	const [vinSuggestions, setvinFilter] = createSuggestionsHook("Vin", { defer: true })
 	createEffect(() => setvinFilter(expedient()?.vin ?? ""))
 
 	return <div class={style.container} ref={setupUndo}>
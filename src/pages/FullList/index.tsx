@@ -2,7 +2,7 @@ import { Show, createEffect, createSignal, onMount } from 'solid-js'
 import IconButton from '../../atoms/IconButton'
 import InputText from '../../atoms/InputText'
 import { utcDateFuture } from '../../database/date'
-import { createHook } from '../../database/expedientHook'
+import { createHook, createSuggestionsHook } from '../../database/expedientHook'
 import { createExpedient } from '../../database/expedientState'
 import { newBlankExpedient } from '../../database/types'
 import { verifyVIN } from '../../database/vin/verify'
@@ -101,16 +101,16 @@ export default function FullList() {
 	}
 
 
-	const [userSuggestions, setUserFilter] = createHook("list_users", "", { defer: true })
+	const [userSuggestions, setUserFilter] = createSuggestionsHook("User", { defer: true })
 	createEffect(() => setUserFilter(inputUser()))
 
-	const [modelSuggestions, setModelFilter] = createHook("list_models", "", { defer: true })
+	const [modelSuggestions, setModelFilter] = createSuggestionsHook("Model", { defer: true })
 	createEffect(() => setModelFilter(inputBody()))
 
-	const [licenseSuggestions, setLicenseFilter] = createHook("list_license_plates", "", { defer: true })
+	const [licenseSuggestions, setLicenseFilter] = createSuggestionsHook("LicensePlate", { defer: true })
 	createEffect(() => setLicenseFilter(inputVIN()))
 
-	const [vinSuggestions, setvinFilter] = createHook("list_vins", "", { defer: true })
+	const [vinSuggestions, setvinFilter] = createSuggestionsHook("Vin", { defer: true })
 	createEffect(() => setvinFilter(inputVIN()))
 
 	const displayPopularity = () => {
@@ -154,6 +154,6 @@ export default function FullList() {
 			</div>
 			<IconButton icon='create from filters' keyMap="Enter" action={create_expedient_from_filters} />
 		</div>
-		<OrderList orderList={() => [...lableOrderListByDate(orderList())]} />
+		<OrderList orderList={() => [...lableOrderListByDate(orderList()?.orders)]} />
 	</>
 }
\ No newline at end of file
@@ -39,9 +39,9 @@ export default function OpenList() {
 
 	// Rename tab
 	createEffect(() => {
-		if (urgentList() && urgentList().length != 0) {
+		if (urgentList() && urgentList().orders.length != 0) {
 			rename(<StaticCheckbox state={"Urgent"} />)
-		} else if (todoList() && todoList().length == 0) {
+		} else if (todoList() && todoList().orders.length == 0) {
 			rename(<StaticCheckbox state={"Done"} />)
 		} else {
 			rename(<StaticCheckbox state={"Todo"} />)
@@ -49,9 +49,9 @@ export default function OpenList() {
 	})
 
 	return <Show when={urgentList() && todoList()}>
-		<Show when={urgentList()?.length || todoList()?.length} fallback={SatisfactionFallback}>
+		<Show when={urgentList()?.orders.length || todoList()?.orders.length} fallback={SatisfactionFallback}>
 			<OrderList orderList={() =>
-				[...lableOrderListByDate(urgentList()), ...lableOrderListByDate(todoList())]
+				[...lableOrderListByDate(urgentList()?.orders), ...lableOrderListByDate(todoList()?.orders)]
 			} />
 		</ Show>
 	</Show>
@@ -225,9 +225,9 @@ export default function PendingList() {
 
 	// Rename tab
 	createEffect(() => {
-		if (instoreList() != null && instoreList().length != 0) {
+		if (instoreList() != null && instoreList().orders.length != 0) {
 			rename(<StaticCheckbox state={"InStore"} />)
-		} else if (awaitingList() != null && awaitingList().length != 0) {
+		} else if (awaitingList() != null && awaitingList().orders.length != 0) {
 			rename(<StaticCheckbox state={"Awaiting"} />)
 		} else {
 			if (isBirthday()) {
@@ -238,8 +238,8 @@ export default function PendingList() {
 		}
 	})
 
-	return <Show when={instoreList()?.length || awaitingList()?.length} fallback={Cake}>
+	return <Show when={instoreList()?.orders.length || awaitingList()?.orders.length} fallback={Cake}>
 		<OrderList orderList={() =>
-			[...lableOrderListByDate(instoreList()), ...lableOrderListByDate(awaitingList())]} />
+			[...lableOrderListByDate(instoreList()?.orders), ...lableOrderListByDate(awaitingList()?.orders)]} />
 	</ Show>
 }
\ No newline at end of file
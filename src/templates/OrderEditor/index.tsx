@@ -4,7 +4,7 @@ import { ContextMenu } from '../../atoms/ContextMenu'
 import InputText from '../../atoms/InputText'
 import InputTextArea from '../../atoms/InputTextArea'
 import { utcDateToString } from '../../database/date'
-import { createHook } from '../../database/expedientHook'
+import { createSuggestionsHook } from '../../database/expedientHook'
 import { updateExpedient } from '../../database/expedientState'
 import { Expedient, ExpedientId, newBlankOrder, Order } from '../../database/types'
 import style from './OrderEditor.module.sass'
@@ -27,7 +27,7 @@ export function OrderEditor(props: Props) {
 		updateExpedient(props.expedientId, { ...expedient })
 	}
 
-	const [titleSuggestions, setTitleFilter] = createHook("list_order_titles", "", { defer: true })
+	const [titleSuggestions, setTitleFilter] = createSuggestionsHook("OrderTitle", { defer: true })
 	createEffect(() => setTitleFilter(order()?.title ?? ""))
 
 	return <ContextMenu